@@ -29,6 +29,7 @@ mod spi1_exti4_gyro;
 mod spi2_exti7_sdcard;
 mod spi3_osd_baro;
 mod stm32f4;
+mod tim3_dshot;
 mod tim7_scheduler;
 mod usart1;
 mod usart6;
@@ -50,17 +51,23 @@ use cortex_m_rt::ExceptionFrame;
 use pro_flight::{
     components::{
         altimeter::Altimeter,
-        cli::{memory, CLI},
-        configuration::FixedWing,
+        arming::ArmingControl,
+        autopilot::Autopilot,
+        cli::{calibrate::EscCalibrator, dshot::DShotCommander, memory, CLI},
+        configuration::{FixedWing, Multirotor, Outputs},
         event::OnEvent,
         event::SchedulableEvent,
+        flight_mode::FlightModeControl,
+        geofence::Geofence,
         imu::IMU,
+        ins::InertialNavigation,
         logger::{self, Level},
         mixer::ControlMixer,
         navigation::Navigation,
         panic::log_panic,
         schedule::{Schedulable, Scheduler},
-        speedometer::Speedometer,
+        stabilizer::Stabilizer,
+        telemetry::crsf::CrsfTelemetry,
         TelemetryUnit,
     },
     config::{self, aircraft::Configuration, Config, SerialConfig},
@@ -68,7 +75,10 @@ use pro_flight::{
         data_source::{AgingStaticData, NoDataSource},
         input::ControlInput,
     },
-    drivers::{accelerometer, barometer, gyroscope, magnetometer, uart::Device, usb_serial},
+    drivers::{
+        accelerometer, barometer, gyroscope, magnetometer, receiver::Receiver, uart::Device,
+        usb_serial,
+    },
     sys::{
         fs::File,
         timer::{self, SysTimer},
@@ -206,15 +216,18 @@ fn main() -> ! {
     .ok();
 
     info!("Initialize ADC VBAT");
-    let battery = adc2_vbat::init(peripherals.ADC2, gpio_c.pc2).reader();
+    let vbat = adc2_vbat::init(peripherals.ADC2, gpio_c.pc2);
 
     let mut gnss: Option<&'static mut Device> = None;
-    let mut receiver: Option<&'static mut Device> = None;
+    let mut receiver: Option<&'static mut Receiver> = None;
 
+    let mut crsf_telemetry = None;
     if let Some(config) = config.peripherals.serials.get("USART1") {
         info!("Initialize USART1");
         let pins = (gpio_a.pa9, gpio_a.pa10);
-        if let Some(device) = usart1::init(peripherals.USART1, pins, &config, clocks) {
+        if let SerialConfig::CRSFTelemetry(_) = config {
+            crsf_telemetry = Some(usart1::init_tx(peripherals.USART1, pins, &config, clocks));
+        } else if let Some(device) = usart1::init(peripherals.USART1, pins, &config, clocks) {
             match device {
                 Device::GNSS(_) => gnss = Some(device),
                 _ => (),
@@ -239,33 +252,17 @@ fn main() -> ! {
 
         let pins = (gpio_c.pc6, gpio_c.pc7);
         let option = usart6::init(peripherals.USART6, pins, &mut nvic, &serial_config, clocks);
-        if let Some(device) = option {
-            match device {
-                Device::SBUS(_) => receiver = Some(device),
-                _ => (),
-            }
-        }
+        receiver = option.map(|device| device.receiver()).flatten();
     }
 
     let mut led = gpio_b.pb5.into_push_pull_output();
     led.set_low().ok();
 
     let mut control_input: Box<dyn AgingStaticData<ControlInput>> = Box::new(NoDataSource::new());
-    if let Some(Device::SBUS(ref mut sbus)) = receiver {
-        control_input = Box::new(sbus.input_reader());
+    if let Some(ref mut receiver) = receiver {
+        control_input = Box::new(receiver.input_reader());
     }
 
-    info!("Initialize PWMs");
-    let tims = (peripherals.TIM1, peripherals.TIM2, peripherals.TIM3, peripherals.TIM5);
-    let pins = (gpio_b.pb0, gpio_b.pb1, gpio_a.pa2, gpio_a.pa3, gpio_a.pa1, gpio_a.pa8);
-    let pwms = pwm::init(tims, pins, clocks, &config.peripherals.pwms);
-    let mixer = ControlMixer::new(control_input, SERVO_SCHEDULE_RATE, NoDataSource::new());
-    let control_surface: Box<dyn OnEvent> = match config.aircraft.configuration {
-        Configuration::Airplane | Configuration::FlyingWing | Configuration::VTail => {
-            Box::new(FixedWing::new(mixer, pwms))
-        }
-    };
-
     let altimeter = Altimeter::new(barometer, barometer::bmp280::SAMPLE_RATE);
     let rate = GYRO_SAMPLE_RATE as u16;
     let mut imu = IMU::new(accelerometer.clone(), gyroscope.clone(), rate);
@@ -276,30 +273,114 @@ fn main() -> ! {
         imu.set_magnetometer(Box::new(magnetometer));
     }
 
-    let mut speedometer =
-        Speedometer::new(altimeter.reader(), imu.as_accelerometer(), GYRO_SAMPLE_RATE);
+    let mut arming = ArmingControl::new(vbat.reader(), imu.calibrated_reader());
+    if let Some(ref mut receiver) = receiver {
+        arming.set_receiver(
+            Box::new(receiver.input_reader()),
+            Box::new(receiver.arm_switch_reader()),
+        );
+    }
+
+    let mut flight_mode = FlightModeControl::new();
+    if let Some(ref mut receiver) = receiver {
+        flight_mode.set_receiver(Box::new(receiver.flight_mode_reader()));
+        flight_mode.set_auxiliary(Box::new(receiver.auxiliary_reader()));
+    }
+
+    let mut ins = InertialNavigation::new(altimeter.reader(), imu.as_accelerometer());
     if let Some(Device::GNSS(ref mut gnss)) = gnss {
-        speedometer.set_gnss(Box::new(gnss.velocity()));
+        ins.set_gnss(Box::new(gnss.position()), Box::new(gnss.velocity()));
+        imu.set_speedometer(Box::new(ins.reader()));
     }
 
-    let mut navigation = Navigation::new(altimeter.reader(), speedometer.reader());
+    let mut navigation = Navigation::new(ins.position_reader(), ins.reader());
     if let Some(Device::GNSS(ref mut gnss)) = gnss {
         navigation.set_gnss(Box::new(gnss.position()));
+        arming.set_gnss(Box::new(gnss.fixed()), Box::new(navigation.mission_reader()));
     }
+    navigation.set_flight_mode(Box::new(flight_mode.reader()));
+    navigation.set_validity(Box::new(ins.validity_reader()));
+
+    let mut geofence =
+        Geofence::new(navigation.reader(), navigation.home_reader(), ins.validity_reader());
+    if config.geofence.file.as_str() != "" {
+        match File::open(config.geofence.file.as_str()) {
+            Ok(mut file) => {
+                geofence.load(&mut file);
+                file.close();
+            }
+            Err(e) => warn!("Open file {} failed: {:?}", config.geofence.file, e),
+        }
+    }
+    flight_mode.set_geofence(Box::new(geofence.reader()));
 
+    let rate = SERVO_SCHEDULE_RATE as u16;
+    let mut stabilizer = Stabilizer::new(imu.reader(), imu.as_gyroscope(), rate);
+    stabilizer.set_flight_mode(Box::new(flight_mode.reader()));
+
+    info!("Initialize PWMs");
+    let tims = (peripherals.TIM1, peripherals.TIM2, peripherals.TIM3, peripherals.TIM5);
+    let pins = (gpio_b.pb0, gpio_b.pb1, gpio_a.pa2, gpio_a.pa3, gpio_a.pa1, gpio_a.pa8);
+    let pwms = pwm::init(tims, pins, clocks, &config.peripherals.pwms);
+    // Receiver considered stale after 100ms, then failsafe takes over
+    let max_age = SERVO_SCHEDULE_RATE / 10;
+    let mut mixer = ControlMixer::new(control_input, max_age, stabilizer.reader());
+    stabilizer.set_control_input(Box::new(mixer.setpoint_reader()));
+    if let Some(ref mut receiver) = receiver {
+        mixer.set_auxiliary(Box::new(receiver.auxiliary_reader()));
+    }
+    let mut autopilot = Autopilot::new(
+        Box::new(altimeter.reader()),
+        Box::new(ins.reader()),
+        Box::new(imu.reader()),
+        rate,
+    );
+    autopilot.set_flight_mode(Box::new(flight_mode.reader()));
+    autopilot.set_navigation(
+        Box::new(navigation.reader()),
+        Box::new(navigation.guidance_reader()),
+        Box::new(ins.validity_reader()),
+    );
+    mixer.set_autopilot(autopilot);
+    flight_mode.set_failsafe(Box::new(mixer.failsafe_reader()));
+    let failsafe = mixer.failsafe_reader();
+    let esc_calibrator = EscCalibrator::new();
+    arming.set_esc_calibration(Box::new(esc_calibrator.reader()));
+    let dshot_commander = DShotCommander::new();
+    let mut outputs = Outputs::new(pwms);
+    outputs.set_arming(Box::new(arming.reader()));
+    outputs.set_esc_calibration(Box::new(esc_calibrator.reader()));
+    outputs.set_dshot_command(Box::new(dshot_commander.reader()));
+    let control_surface: Box<dyn OnEvent> = match config.aircraft.configuration {
+        Configuration::Airplane | Configuration::FlyingWing | Configuration::VTail => {
+            Box::new(FixedWing::new(mixer, outputs))
+        }
+        Configuration::QuadX
+        | Configuration::QuadPlus
+        | Configuration::Hexa
+        | Configuration::Tricopter => Box::new(Multirotor::new(mixer, outputs)),
+    };
+
+    let raw_sensors = (accelerometer.clone(), gyroscope.clone());
     let mut telemetry = TelemetryUnit::new(
         altimeter.reader(),
-        battery,
+        vbat.reader(),
         accelerometer,
         gyroscope,
         imu.reader(),
-        speedometer.reader(),
+        ins.reader(),
         navigation.reader(),
     );
-    if let Some(Device::SBUS(ref mut sbus)) = receiver {
-        telemetry.set_rssi(Box::new(sbus.rssi_reader()));
-        telemetry.set_control_input(Box::new(sbus.input_reader()));
+    if let Some(ref mut receiver) = receiver {
+        telemetry.set_rssi(Box::new(receiver.rssi_reader()));
+        telemetry.set_control_input(Box::new(receiver.input_reader()));
     }
+    telemetry.set_flight_mode(Box::new(flight_mode.reader()));
+    telemetry.set_arming(Box::new(arming.reader()));
+    telemetry.set_failsafe(Box::new(failsafe));
+    telemetry.set_guidance(Box::new(navigation.guidance_reader()));
+    telemetry.set_geofence(Box::new(geofence.reader()));
+    telemetry.set_ins_validity(Box::new(ins.validity_reader()));
     if let Some(magnetometer) = magnetometer::get_data_source() {
         telemetry.set_magnetometer(Box::new(magnetometer));
     }
@@ -319,19 +400,24 @@ fn main() -> ! {
     let (baro, osd) = result.ok().unwrap();
 
     let trigger = exti0_softirq::init(&mut peripherals.EXTI, control_surface);
-    if let Some(Device::SBUS(ref mut sbus)) = receiver {
-        sbus.set_notify(Box::new(trigger.clone()));
+    if let Some(ref mut receiver) = receiver {
+        receiver.set_notify(Box::new(trigger.clone()));
     }
 
     let telemetry_source = telemetry.reader();
+    let crsf_telemetry = crsf_telemetry.map(|tx| CrsfTelemetry::new(telemetry.reader(), tx));
     let servo_trigger = SchedulableEvent::new(trigger, SERVO_SCHEDULE_RATE);
 
     let mut tasks: Vec<Box<dyn Schedulable>> = vec![
         Box::new(baro),
         Box::new(altimeter),
         Box::new(imu),
-        Box::new(speedometer),
+        Box::new(arming),
+        Box::new(flight_mode),
+        Box::new(stabilizer),
+        Box::new(ins),
         Box::new(navigation),
+        Box::new(geofence),
         Box::new(servo_trigger),
         Box::new(telemetry),
         Box::new(osd),
@@ -339,10 +425,16 @@ fn main() -> ! {
     if let Some(i2c2) = i2c2.take() {
         tasks.insert(0, Box::new(i2c2));
     }
+    if let Some(crsf_telemetry) = crsf_telemetry {
+        tasks.push(Box::new(crsf_telemetry));
+    }
     let group = Scheduler::new(tasks, 200);
     tim7_scheduler::init(peripherals.TIM7, Box::new(group), clocks, 200);
 
     let mut cli = CLI::new(telemetry_source, reboot, bootloader, free);
+    cli.set_esc_calibrator(esc_calibrator);
+    cli.set_accel_calibrator(Box::new(raw_sensors.0), Box::new(raw_sensors.1));
+    cli.set_dshot_commander(dshot_commander);
     let mut timer = SysTimer::new();
     let mut led_on = false;
     led.set_high().ok();
@@ -357,6 +449,8 @@ fn main() -> ! {
             led_on = !led_on;
         }
 
+        cli.expire();
+        cli.collect(&mut serial).ok();
         if !device.poll(&mut [&mut serial.0]) {
             continue;
         }
@@ -1,10 +1,8 @@
-use core::cmp::min;
-
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 
 use embedded_hal::PwmPin;
-use pro_flight::config::peripherals::pwm::PWMs as Config;
+use pro_flight::config::peripherals::pwm::{PWMs as Config, Protocol};
 use stm32f4xx_hal::gpio::gpioa::{PA1, PA2, PA3, PA8};
 use stm32f4xx_hal::gpio::gpiob::{PB0, PB1};
 use stm32f4xx_hal::gpio::{Floating, Input};
@@ -12,42 +10,105 @@ use stm32f4xx_hal::pwm;
 use stm32f4xx_hal::rcc::Clocks;
 use stm32f4xx_hal::{prelude::*, stm32};
 
+use crate::tim3_dshot::{Channel, DShot};
+
 type Default = Input<Floating>;
 type PWMs = (stm32::TIM1, stm32::TIM2, stm32::TIM3, stm32::TIM5);
 type PINs = (PB0<Default>, PB1<Default>, PA2<Default>, PA3<Default>, PA1<Default>, PA8<Default>);
-type PwmPins = Vec<(&'static str, Box<dyn PwmPin<Duty = u16>>)>;
+type Pin = Box<dyn PwmPin<Duty = u16>>;
+type PwmPins = Vec<(&'static str, Pin, u16)>;
 
-pub fn init(pwms: PWMs, pins: PINs, clocks: Clocks, cfg: &Config) -> PwmPins {
-    let rate1 = cfg.get("PWM1").map(|o| o.rate()).unwrap_or(50) as u32;
-    let rate2 = cfg.get("PWM2").map(|o| o.rate()).unwrap_or(50) as u32;
+fn protocol_of(cfg: &Config, name: &str) -> Option<Protocol> {
+    cfg.get(name).map(|pwm| pwm.protocol())
+}
+
+// Outputs sharing same timer must agree on protocol, otherwise the second one is rejected
+fn timer_protocol(cfg: &Config, first: &str, second: &str) -> (Protocol, bool) {
+    match (protocol_of(cfg, first), protocol_of(cfg, second)) {
+        (Some(a), Some(b)) if a != b => {
+            error!("{} protocol {} conflicts with {} protocol {}", second, b, first, a);
+            (a, true)
+        }
+        (Some(protocol), _) | (None, Some(protocol)) => (protocol, false),
+        (None, None) => (Protocol::PWM, false),
+    }
+}
+
+fn timer_rate(cfg: &Config, protocol: Protocol, names: &[&str]) -> u32 {
+    if let Some(rate) = protocol.dshot_rate() {
+        return rate as u32 * 1000;
+    }
+    let configs = names.iter().filter_map(|&name| cfg.get(name));
+    let rates = configs.filter(|pwm| pwm.protocol() == protocol).map(|pwm| pwm.rate() as u32);
+    rates.min().unwrap_or(50)
+}
 
+// Duty of analog outputs is calculated with actual timer rate,
+// which is lowest rate among outputs sharing the timer
+fn output_rate(protocol: Protocol, timer_rate: u32) -> u16 {
+    match protocol.dshot_rate() {
+        Some(_) => 0, // unused by digital protocols
+        None => timer_rate as u16,
+    }
+}
+
+pub fn init(pwms: PWMs, pins: PINs, clocks: Clocks, cfg: &Config) -> PwmPins {
     let (tim1, tim2, tim3, tim5) = pwms;
     let (pb0, pb1, pa2, pa3, pa1, pa8) = pins;
+    let mut rejected: Vec<&'static str> = Vec::new();
+
     let pb0_1 = (pb0.into_alternate_af2(), pb1.into_alternate_af2());
-    let rate = if rate1 > 0 && rate2 > 0 { min(rate1, rate2) } else { rate1 + rate2 };
+    let (protocol, conflict) = timer_protocol(cfg, "PWM1", "PWM2");
+    if conflict {
+        rejected.push("PWM2");
+    }
+    let rate = timer_rate(cfg, protocol, &["PWM1", "PWM2"]);
     let (pwm1, pwm2) = pwm::tim3(tim3, pb0_1, clocks, rate.hz());
+    let rate1_2 = output_rate(protocol, rate);
+    let (pwm1, pwm2): (Pin, Pin) = match protocol.dshot_rate() {
+        Some(_) => {
+            (Box::new(DShot::new(pwm1, Channel::C3)), Box::new(DShot::new(pwm2, Channel::C4)))
+        }
+        None => (Box::new(pwm1), Box::new(pwm2)),
+    };
+
+    // DShot requires DMA, which is only wired for TIM3
+    for &name in ["PWM3", "PWM4", "PWM5", "PWM6"].iter() {
+        if protocol_of(cfg, name).map(|p| p.dshot_rate().is_some()).unwrap_or(false) {
+            error!("DShot not supported on {}", name);
+            rejected.push(name);
+        }
+    }
 
-    let rate3 = cfg.get("PWM3").map(|o| o.rate()).unwrap_or(50) as u32;
-    let rate4 = cfg.get("PWM4").map(|o| o.rate()).unwrap_or(50) as u32;
     let pa2_3 = (pa2.into_alternate_af1(), pa3.into_alternate_af1());
-    let rate = if rate3 > 0 && rate4 > 0 { min(rate3, rate4) } else { rate3 + rate4 };
+    let (protocol, conflict) = timer_protocol(cfg, "PWM3", "PWM4");
+    if conflict {
+        rejected.push("PWM4");
+    }
+    let rate = timer_rate(cfg, protocol, &["PWM3", "PWM4"]);
     let (pwm4, pwm3) = pwm::tim2(tim2, pa2_3, clocks, rate.hz());
+    let rate3_4 = output_rate(protocol, rate);
 
-    let rate = cfg.get("PWM5").map(|o| o.rate()).unwrap_or(50) as u32;
+    let protocol = protocol_of(cfg, "PWM5").unwrap_or(Protocol::PWM);
+    let rate = timer_rate(cfg, protocol, &["PWM5"]);
     let pwm5 = pwm::tim5(tim5, pa1.into_alternate_af2(), clocks, rate.hz());
-    let rate = cfg.get("PWM6").map(|o| o.rate()).unwrap_or(50) as u32;
+    let rate5 = output_rate(protocol, rate);
+    let protocol = protocol_of(cfg, "PWM6").unwrap_or(Protocol::PWM);
+    let rate = timer_rate(cfg, protocol, &["PWM6"]);
     let pwm6 = pwm::tim1(tim1, pa8.into_alternate_af1(), clocks, rate.hz());
+    let rate6 = output_rate(protocol, rate);
 
-    let mut pwms: Vec<(&'static str, Box<dyn PwmPin<Duty = u16>>)> = vec![
-        ("PWM1", Box::new(pwm1)),
-        ("PWM2", Box::new(pwm2)),
-        ("PWM3", Box::new(pwm3)),
-        ("PWM4", Box::new(pwm4)),
-        ("PWM5", Box::new(pwm5)),
-        ("PWM6", Box::new(pwm6)),
+    let mut pwms: PwmPins = vec![
+        ("PWM1", pwm1, rate1_2),
+        ("PWM2", pwm2, rate1_2),
+        ("PWM3", Box::new(pwm3), rate3_4),
+        ("PWM4", Box::new(pwm4), rate3_4),
+        ("PWM5", Box::new(pwm5), rate5),
+        ("PWM6", Box::new(pwm6), rate6),
     ];
+    pwms.retain(|(name, _, _)| !rejected.contains(name));
 
-    for (_, pwm) in pwms.iter_mut() {
+    for (_, pwm, _) in pwms.iter_mut() {
         pwm.enable();
     }
     pwms
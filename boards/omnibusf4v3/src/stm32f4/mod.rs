@@ -1,6 +1,7 @@
 use alloc::boxed::Box;
 
 use pro_flight::config::SerialConfig;
+use pro_flight::drivers::ibus::IBUS_BAUDRATE;
 use stm32f4xx_hal::prelude::*;
 use stm32f4xx_hal::serial::config::{Config, DmaConfig, Parity, StopBits, WordLength};
 
@@ -16,6 +17,15 @@ pub fn to_serial_config(config: &SerialConfig) -> Config {
             wordlength: WordLength::DataBits9,
             dma: DmaConfig::TxRx,
         },
+        SerialConfig::CRSF(crsf) => {
+            Config { baudrate: crsf.baudrate.bps(), dma: DmaConfig::Rx, ..Default::default() }
+        }
+        SerialConfig::IBUS => {
+            Config { baudrate: IBUS_BAUDRATE.bps(), dma: DmaConfig::Rx, ..Default::default() }
+        }
+        SerialConfig::CRSFTelemetry(crsf) => {
+            Config { baudrate: crsf.baudrate.bps(), ..Default::default() }
+        }
     }
 }
 
@@ -23,5 +33,7 @@ pub fn alloc_by_config(config: &SerialConfig) -> Box<[u8]> {
     match config {
         SerialConfig::GNSS(_) => Box::new([0u8; 128 + 2]),
         SerialConfig::SBUS(_) => Box::new([0u8; 64 + 2]),
+        SerialConfig::CRSF(_) | SerialConfig::CRSFTelemetry(_) => Box::new([0u8; 64 + 2]),
+        SerialConfig::IBUS => Box::new([0u8; 64 + 2]),
     }
 }
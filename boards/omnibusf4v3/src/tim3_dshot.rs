@@ -0,0 +1,80 @@
+use embedded_hal::PwmPin;
+use pro_flight::drivers::dshot::{to_dma_buffer, BUFFER_SIZE};
+use stm32f4xx_hal::stm32;
+
+#[derive(Copy, Clone)]
+pub enum Channel {
+    C3, // dma1 stream 7 channel 5
+    C4, // dma1 stream 2 channel 5
+}
+
+/// Wraps TIM3 PWM channel, takes DShot frame as duty and
+/// feeds compare register through DMA bit by bit
+pub struct DShot<P> {
+    pwm: P,
+    channel: Channel,
+    frame: u16,
+    dma_buffer: [u16; BUFFER_SIZE],
+}
+
+impl<P: PwmPin<Duty = u16>> DShot<P> {
+    pub fn new(pwm: P, channel: Channel) -> Self {
+        let tim3 = unsafe { &*stm32::TIM3::ptr() };
+        match channel {
+            Channel::C3 => tim3.dier.modify(|_, w| w.cc3de().set_bit()),
+            Channel::C4 => tim3.dier.modify(|_, w| w.cc4de().set_bit()),
+        }
+        Self { pwm, channel, frame: 0, dma_buffer: [0u16; BUFFER_SIZE] }
+    }
+
+    fn transfer(&mut self) {
+        let tim3 = unsafe { &*stm32::TIM3::ptr() };
+        let dma1 = unsafe { &*stm32::DMA1::ptr() };
+        let (stream, register) = match self.channel {
+            Channel::C3 => {
+                dma1.hifcr.write(|w| unsafe { w.bits(0x3D << 22) });
+                (&dma1.st[7], &tim3.ccr3 as *const _ as u32)
+            }
+            Channel::C4 => {
+                dma1.lifcr.write(|w| unsafe { w.bits(0x3D << 16) });
+                (&dma1.st[2], &tim3.ccr4 as *const _ as u32)
+            }
+        };
+        stream.cr.modify(|_, w| w.en().disabled());
+        stream.par.write(|w| w.pa().bits(register));
+        stream.m0ar.write(|w| w.m0a().bits(self.dma_buffer.as_ptr() as u32));
+        stream.ndtr.write(|w| w.ndt().bits(BUFFER_SIZE as u16));
+        #[rustfmt::skip]
+        stream.cr.write(|w| {
+            w.chsel().bits(5).minc().incremented().dir().memory_to_peripheral()
+                .msize().bits16().psize().bits16().en().enabled()
+        });
+    }
+}
+
+impl<P: PwmPin<Duty = u16>> PwmPin for DShot<P> {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        self.pwm.disable()
+    }
+
+    fn enable(&mut self) {
+        self.pwm.set_duty(0);
+        self.pwm.enable()
+    }
+
+    fn get_duty(&self) -> u16 {
+        self.frame
+    }
+
+    fn get_max_duty(&self) -> u16 {
+        u16::MAX
+    }
+
+    fn set_duty(&mut self, frame: u16) {
+        self.frame = frame;
+        to_dma_buffer(frame, self.pwm.get_max_duty(), &mut self.dma_buffer);
+        self.transfer();
+    }
+}
@@ -7,7 +7,7 @@ use stm32f4xx_hal::gpio::gpioa;
 use stm32f4xx_hal::gpio::{Floating, Input};
 use stm32f4xx_hal::interrupt;
 use stm32f4xx_hal::rcc::Clocks;
-use stm32f4xx_hal::serial::Serial;
+use stm32f4xx_hal::serial::{Serial, Tx};
 use stm32f4xx_hal::stm32;
 
 use crate::stm32f4::{alloc_by_config, to_serial_config};
@@ -81,3 +81,16 @@ pub fn init(
     unsafe { DEVICE = Some(device) }
     unsafe { DEVICE.as_mut() }
 }
+
+pub fn init_tx(
+    usart1: stm32::USART1,
+    pins: (PA9, PA10),
+    config: &SerialConfig,
+    clocks: Clocks,
+) -> Tx<stm32::USART1> {
+    let (pa9, pa10) = pins;
+    let pins = (pa9.into_alternate_af7(), pa10.into_alternate_af7());
+    let serial = Serial::usart1(usart1, pins, to_serial_config(&config), clocks).unwrap();
+    let (tx, _) = serial.split();
+    tx
+}
@@ -1,6 +1,8 @@
 use alloc::boxed::Box;
 
 use pro_flight::config::SerialConfig;
+use pro_flight::drivers::crsf::CrsfReceiver;
+use pro_flight::drivers::ibus::IbusReceiver;
 use pro_flight::drivers::sbus::SbusReceiver;
 use pro_flight::drivers::uart::Device;
 use stm32f4xx_hal::gpio::gpioc;
@@ -82,6 +84,14 @@ pub fn init(
             unsafe { nvic.set_priority(stm32::Interrupt::DMA2_STREAM1, 18) }; // No less than DMA1 stream0
             Device::SBUS(SbusReceiver::new())
         }
+        SerialConfig::CRSF(_) => {
+            unsafe { nvic.set_priority(stm32::Interrupt::DMA2_STREAM1, 18) };
+            Device::CRSF(CrsfReceiver::new())
+        }
+        SerialConfig::IBUS => {
+            unsafe { nvic.set_priority(stm32::Interrupt::DMA2_STREAM1, 18) };
+            Device::IBUS(IbusReceiver::new())
+        }
         _ => return None,
     };
     unsafe { DEVICE = Some(device) }
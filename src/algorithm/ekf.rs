@@ -0,0 +1,224 @@
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::{Matrix3, Matrix3x6, Matrix6, Quaternion, RowVector6, UnitQuaternion, Vector3};
+
+use super::mahony::MagnetismOrHeading;
+use super::wrap_angle;
+
+const INITIAL_ATTITUDE_VARIANCE: f32 = 1.0; // rad²
+const INITIAL_BIAS_VARIANCE: f32 = 0.001; // (rad/s)²
+
+/// Multiplicative extended kalman filter with attitude error and gyro bias as states,
+/// attitude error is small rotation in body frame injected into quaternion after each correction
+///
+/// Accelerometer corrects roll and pitch,
+/// magnetometer or GNSS heading corrects yaw as a scalar heading measurement
+pub struct EKF {
+    interval: f32,
+    magnetic_declination: f32,
+    gyro_noise: f32,
+    gyro_bias_noise: f32,
+    accel_noise: f32,
+    heading_noise: f32,
+    quaternion: UnitQuaternion<f32>,
+    gyro_bias: Vector3<f32>,
+    covariance: Matrix6<f32>,
+}
+
+impl EKF {
+    pub fn new(sample_rate: f32, magnetic_declination: f32) -> Self {
+        let mut covariance = Matrix6::zeros();
+        for i in 0..3 {
+            covariance[(i, i)] = INITIAL_ATTITUDE_VARIANCE;
+            covariance[(i + 3, i + 3)] = INITIAL_BIAS_VARIANCE;
+        }
+        Self {
+            interval: 1.0 / sample_rate,
+            magnetic_declination: magnetic_declination.to_radians(),
+            gyro_noise: 0.01,
+            gyro_bias_noise: 0.0002,
+            accel_noise: 0.05,
+            heading_noise: 0.1,
+            quaternion: UnitQuaternion::new_unchecked(Quaternion::new(1.0, 0.0, 0.0, 0.0)),
+            gyro_bias: Vector3::new(0.0, 0.0, 0.0),
+            covariance,
+        }
+    }
+
+    /// Gyro noise in rad/s and gyro bias random walk in rad/s²
+    pub fn set_process_noise(&mut self, gyro_noise: f32, gyro_bias_noise: f32) {
+        self.gyro_noise = gyro_noise;
+        self.gyro_bias_noise = gyro_bias_noise;
+    }
+
+    /// Accelerometer noise in g and heading noise in radian
+    pub fn set_measurement_noise(&mut self, accel_noise: f32, heading_noise: f32) {
+        self.accel_noise = accel_noise;
+        self.heading_noise = heading_noise;
+    }
+
+    pub fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.quaternion
+    }
+
+    /// Estimated gyro bias in rad/s
+    pub fn gyro_bias(&self) -> Vector3<f32> {
+        self.gyro_bias
+    }
+
+    fn predict(&mut self, rate: &Vector3<f32>) {
+        let q = self.quaternion;
+        let q_derivate = 0.5 * q.into_inner() * Quaternion::from_parts(0.0, *rate);
+        self.quaternion =
+            UnitQuaternion::new_normalize(q.into_inner() + q_derivate * self.interval);
+
+        let dt = self.interval;
+        let skew = rate.cross_matrix();
+        let mut transition = Matrix6::identity();
+        for i in 0..3 {
+            for j in 0..3 {
+                transition[(i, j)] -= skew[(i, j)] * dt;
+            }
+            transition[(i, i + 3)] = -dt;
+        }
+        let mut noise = Matrix6::zeros();
+        for i in 0..3 {
+            noise[(i, i)] = self.gyro_noise * self.gyro_noise * dt;
+            noise[(i + 3, i + 3)] = self.gyro_bias_noise * self.gyro_bias_noise * dt;
+        }
+        let p = transition * self.covariance * transition.transpose() + noise;
+        self.covariance = (p + p.transpose()) * 0.5;
+    }
+
+    fn inject(&mut self, error: &[f32; 6]) {
+        let (x, y, z) = (error[0] / 2.0, error[1] / 2.0, error[2] / 2.0);
+        let delta = UnitQuaternion::new_normalize(Quaternion::new(1.0, x, y, z));
+        self.quaternion = self.quaternion * delta;
+        self.gyro_bias += Vector3::new(error[3], error[4], error[5]);
+    }
+
+    fn correct_acceleration(&mut self, acceleration: &Vector3<f32>) {
+        let down = self.quaternion.inverse_transform_vector(&Vector3::new(0.0, 0.0, -1.0));
+        let skew = down.cross_matrix();
+        let h = Matrix3x6::from_fn(|i, j| if j < 3 { skew[(i, j)] } else { 0.0 });
+        let residual = acceleration - down;
+        let variance = self.accel_noise * self.accel_noise;
+        let s = h * self.covariance * h.transpose() + Matrix3::identity() * variance;
+        let s_inverse = match s.try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let k = self.covariance * h.transpose() * s_inverse;
+        let error = k * residual;
+        self.covariance = (Matrix6::identity() - k * h) * self.covariance;
+        self.inject(&[error[0], error[1], error[2], error[3], error[4], error[5]]);
+    }
+
+    fn correct_heading(&mut self, residual: f32) {
+        let q = self.quaternion;
+        let nose = q.transform_vector(&Vector3::new(0.0, 1.0, 0.0));
+        let horizontal = nose[0] * nose[0] + nose[1] * nose[1];
+        if horizontal < 0.01 {
+            return;
+        }
+        // Derivative of heading over nose direction, then nose direction over attitude error
+        let gradient = Vector3::new(nose[1], -nose[0], 0.0) / horizontal;
+        let mut h = RowVector6::zeros();
+        for i in 0..3 {
+            let mut axis = Vector3::new(0.0, 0.0, 0.0);
+            axis[i] = 1.0;
+            h[i] = gradient.dot(&q.transform_vector(&axis.cross(&Vector3::y())));
+        }
+        let variance = self.heading_noise * self.heading_noise;
+        let s = (h * self.covariance * h.transpose())[0] + variance;
+        let k = self.covariance * h.transpose() / s;
+        let error = k * residual;
+        self.covariance = (Matrix6::identity() - k * h) * self.covariance;
+        self.inject(&[error[0], error[1], error[2], error[3], error[4], error[5]]);
+    }
+
+    fn heading_residual(&self, magnetism_or_heading: MagnetismOrHeading) -> Option<f32> {
+        let q = &self.quaternion;
+        match magnetism_or_heading {
+            MagnetismOrHeading::Magnetism(magnetism) => {
+                let estimated = q.transform_vector(&magnetism.try_normalize(0.0)?);
+                if estimated[0] * estimated[0] + estimated[1] * estimated[1] < 0.01 {
+                    return None;
+                }
+                Some(wrap_angle(self.magnetic_declination - estimated[0].atan2(estimated[1])))
+            }
+            MagnetismOrHeading::Heading(heading) => {
+                let nose = q.transform_vector(&Vector3::new(0.0, 1.0, 0.0));
+                Some(wrap_angle(heading.to_radians() - nose[0].atan2(nose[1])))
+            }
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        gyro: &Vector3<f32>,
+        acceleration: &Vector3<f32>,
+        magnetism: Option<MagnetismOrHeading>,
+    ) -> Option<UnitQuaternion<f32>> {
+        let acceleration = acceleration.try_normalize(0.0)?;
+        self.predict(&(gyro - self.gyro_bias));
+        self.correct_acceleration(&acceleration);
+        if let Some(residual) = magnetism.map(|m| self.heading_residual(m)).flatten() {
+            self.correct_heading(residual);
+        }
+        Some(self.quaternion)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_ekf_convergence() {
+        use nalgebra::Vector3;
+
+        use crate::algorithm::mahony::MagnetismOrHeading;
+        use crate::algorithm::rotation;
+        use crate::datastructures::measurement::euler::Euler;
+
+        use super::EKF;
+
+        // Far from initial level attitude, heading 120 with 40 degree bank and 20 degree pitch
+        let attitude = rotation(-Vector3::z(), 120.0)
+            * rotation(Vector3::x(), 20.0)
+            * rotation(Vector3::y(), 40.0);
+        let gyro = Vector3::new(0.0, 0.0, 0.0);
+        let accel = attitude.inverse_transform_vector(&Vector3::new(0.0, 0.0, -1.0));
+        let heading = Some(MagnetismOrHeading::Heading(120.0));
+
+        let mut ekf = EKF::new(100.0, 0.0);
+        for _ in 0..10 * 100 {
+            ekf.update(&gyro, &accel, heading);
+        }
+        let expected = Euler::from(attitude);
+        let euler = Euler::from(ekf.quaternion());
+        assert!((euler.roll - expected.roll).abs() < 0.02);
+        assert!((euler.pitch - expected.pitch).abs() < 0.02);
+        assert!((euler.yaw - expected.yaw).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_ekf_gyro_bias() {
+        use nalgebra::Vector3;
+
+        use crate::datastructures::measurement::euler::Euler;
+
+        use super::EKF;
+        use crate::algorithm::mahony::MagnetismOrHeading;
+
+        let bias = Vector3::new(0.01, -0.02, 0.015);
+        let accel = Vector3::new(0.0, 0.0, -1.0);
+        let magnetism = Some(MagnetismOrHeading::Magnetism(Vector3::new(0.0, 0.4, -0.9)));
+
+        let mut ekf = EKF::new(100.0, 0.0);
+        for _ in 0..60 * 100 {
+            ekf.update(&bias, &accel, magnetism);
+        }
+        assert!((ekf.gyro_bias() - bias).norm() < 0.002);
+        let euler = Euler::from(ekf.quaternion());
+        assert!(euler.roll.abs() < 0.01 && euler.pitch.abs() < 0.01 && euler.yaw.abs() < 0.01);
+    }
+}
@@ -0,0 +1,198 @@
+use nalgebra::{Matrix3, Vector3};
+
+const INITIAL_POSITION_VARIANCE: f32 = 100.0; // m²
+const INITIAL_VELOCITY_VARIANCE: f32 = 25.0; // (m/s)²
+const INITIAL_BIAS_VARIANCE: f32 = 0.25; // (m/s²)²
+
+// Position, velocity and accelerometer bias along one earth axis
+#[derive(Copy, Clone)]
+struct Axis {
+    state: Vector3<f32>,
+    covariance: Matrix3<f32>,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        let mut covariance = Matrix3::zeros();
+        covariance[(0, 0)] = INITIAL_POSITION_VARIANCE;
+        covariance[(1, 1)] = INITIAL_VELOCITY_VARIANCE;
+        covariance[(2, 2)] = INITIAL_BIAS_VARIANCE;
+        Self { state: Vector3::new(0.0, 0.0, 0.0), covariance }
+    }
+}
+
+impl Axis {
+    fn predict(&mut self, acceleration: f32, dt: f32, accel_noise: f32, bias_noise: f32) {
+        let a = acceleration - self.state[2];
+        self.state[0] += self.state[1] * dt + a * dt * dt / 2.0;
+        self.state[1] += a * dt;
+
+        let mut transition = Matrix3::identity();
+        transition[(0, 1)] = dt;
+        transition[(0, 2)] = -dt * dt / 2.0;
+        transition[(1, 2)] = -dt;
+        let g = Vector3::new(dt * dt / 2.0, dt, 0.0);
+        let mut noise = g * g.transpose() * accel_noise * accel_noise;
+        noise[(2, 2)] = bias_noise * bias_noise * dt;
+        let p = transition * self.covariance * transition.transpose() + noise;
+        self.covariance = (p + p.transpose()) * 0.5;
+    }
+
+    // Scalar measurement of state at index
+    fn correct(&mut self, index: usize, measurement: f32, noise: f32) {
+        let s = self.covariance[(index, index)] + noise * noise;
+        let k = self.covariance.column(index) / s;
+        self.state += k * (measurement - self.state[index]);
+        let p = self.covariance - k * self.covariance.row(index);
+        self.covariance = (p + p.transpose()) * 0.5;
+    }
+
+    fn reset(&mut self, position: f32) {
+        let velocity_variance = self.covariance[(1, 1)];
+        let bias_variance = self.covariance[(2, 2)];
+        self.state[0] = position;
+        self.covariance = Matrix3::zeros();
+        self.covariance[(0, 0)] = INITIAL_POSITION_VARIANCE;
+        self.covariance[(1, 1)] = velocity_variance;
+        self.covariance[(2, 2)] = bias_variance;
+    }
+}
+
+/// Loosely coupled kalman filter with position, velocity and accelerometer bias as states,
+/// each earth axis is independent since acceleration is already in earth frame
+///
+/// Propagates with earth frame acceleration in m/s² without gravity,
+/// corrects with GNSS position and velocity and barometer altitude
+pub struct INS {
+    axes: [Axis; 3],
+    accel_noise: f32,
+    accel_bias_noise: f32,
+    gnss_position_noise: f32,
+    gnss_velocity_noise: f32,
+    baro_noise: f32,
+}
+
+impl INS {
+    pub fn new() -> Self {
+        Self {
+            axes: [Axis::default(); 3],
+            accel_noise: 0.5,
+            accel_bias_noise: 0.01,
+            gnss_position_noise: 3.0,
+            gnss_velocity_noise: 0.5,
+            baro_noise: 1.0,
+        }
+    }
+
+    /// Accelerometer noise in m/s² and accelerometer bias random walk in m/s³
+    pub fn set_process_noise(&mut self, accel_noise: f32, accel_bias_noise: f32) {
+        self.accel_noise = accel_noise;
+        self.accel_bias_noise = accel_bias_noise;
+    }
+
+    /// GNSS position noise in meter, GNSS velocity noise in m/s and barometer noise in meter
+    pub fn set_measurement_noise(&mut self, position: f32, velocity: f32, baro: f32) {
+        self.gnss_position_noise = position;
+        self.gnss_velocity_noise = velocity;
+        self.baro_noise = baro;
+    }
+
+    /// East, north and up in meter relative to origin
+    pub fn position(&self) -> Vector3<f32> {
+        Vector3::from_fn(|i, _| self.axes[i].state[0])
+    }
+
+    pub fn velocity(&self) -> Vector3<f32> {
+        Vector3::from_fn(|i, _| self.axes[i].state[1])
+    }
+
+    /// Estimated accelerometer bias in m/s²
+    pub fn accel_bias(&self) -> Vector3<f32> {
+        Vector3::from_fn(|i, _| self.axes[i].state[2])
+    }
+
+    pub fn position_variance(&self) -> Vector3<f32> {
+        Vector3::from_fn(|i, _| self.axes[i].covariance[(0, 0)])
+    }
+
+    pub fn velocity_variance(&self) -> Vector3<f32> {
+        Vector3::from_fn(|i, _| self.axes[i].covariance[(1, 1)])
+    }
+
+    pub fn predict(&mut self, acceleration: &Vector3<f32>, dt: f32) {
+        for i in 0..3 {
+            self.axes[i].predict(acceleration[i], dt, self.accel_noise, self.accel_bias_noise);
+        }
+    }
+
+    /// East and north in meter
+    pub fn correct_position(&mut self, x: f32, y: f32) {
+        self.axes[0].correct(0, x, self.gnss_position_noise);
+        self.axes[1].correct(0, y, self.gnss_position_noise);
+    }
+
+    /// East and north in m/s, vertical speed is left to barometer
+    pub fn correct_velocity(&mut self, x: f32, y: f32) {
+        self.axes[0].correct(1, x, self.gnss_velocity_noise);
+        self.axes[1].correct(1, y, self.gnss_velocity_noise);
+    }
+
+    /// Height in meter relative to origin
+    pub fn correct_altitude(&mut self, height: f32) {
+        self.axes[2].correct(0, height, self.baro_noise);
+    }
+
+    /// Moves horizontal origin, e.g. on first GNSS fix
+    pub fn reset_position(&mut self, x: f32, y: f32) {
+        self.axes[0].reset(x);
+        self.axes[1].reset(y);
+    }
+}
+
+mod test {
+    #[test]
+    fn test_ins_convergence() {
+        use nalgebra::Vector3;
+
+        use super::INS;
+
+        let mut ins = INS::new();
+        let velocity = Vector3::new(10.0, -5.0, 1.0);
+        let mut position = Vector3::new(0.0, 0.0, 0.0);
+        let acceleration = Vector3::new(0.0, 0.0, 0.0);
+        for i in 0..60 * 50 {
+            position += velocity / 50.0;
+            ins.predict(&acceleration, 1.0 / 50.0);
+            ins.correct_altitude(position[2]);
+            if i % 10 == 0 {
+                ins.correct_position(position[0], position[1]);
+                ins.correct_velocity(velocity[0], velocity[1]);
+            }
+        }
+        assert!((ins.position() - position).norm() < 1.0);
+        assert!((ins.velocity() - velocity).norm() < 0.1);
+        let variance = ins.position_variance();
+        assert!(variance[0] < 9.0 && variance[1] < 9.0 && variance[2] < 1.0);
+    }
+
+    #[test]
+    fn test_ins_accel_bias() {
+        use nalgebra::Vector3;
+
+        use super::INS;
+
+        let mut ins = INS::new();
+        let bias = Vector3::new(0.2, -0.1, 0.3);
+        for i in 0..120 * 50 {
+            // Stationary with biased accelerometer
+            ins.predict(&bias, 1.0 / 50.0);
+            ins.correct_altitude(0.0);
+            if i % 10 == 0 {
+                ins.correct_position(0.0, 0.0);
+                ins.correct_velocity(0.0, 0.0);
+            }
+        }
+        assert!((ins.accel_bias() - bias).norm() < 0.02);
+        assert!(ins.velocity().norm() < 0.05);
+    }
+}
@@ -0,0 +1,165 @@
+use core::f32::consts::{FRAC_PI_2, PI};
+
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::Vector2;
+
+use crate::datastructures::measurement::GRAVITY;
+
+use super::wrap_angle;
+
+// Below this ground speed course over ground is meaningless
+const MIN_SPEED: f32 = 1.0;
+
+/// Lateral guidance steering towards a reference point L1 ahead on the track,
+/// where L1 = damping * period * ground speed / π,
+/// lateral acceleration demand is 2V²/L1 * sin(η), η being angle from velocity to reference point
+#[derive(Copy, Clone, Debug)]
+pub struct L1 {
+    period: f32,
+    damping: f32,
+    max_bank: f32,
+}
+
+impl L1 {
+    pub fn new(period: f32, damping: f32, max_bank: f32) -> Self {
+        Self { period, damping, max_bank }
+    }
+
+    fn distance(&self, speed: f32) -> f32 {
+        self.damping * self.period * speed / PI
+    }
+
+    // Lateral acceleration towards reference point, distance to reference no less than `min`
+    fn steer(
+        &self,
+        reference: Vector2<f32>,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        min: f32,
+    ) -> (f32, f32) {
+        let speed = velocity.norm();
+        let desired = reference - position;
+        let course = desired.x.atan2(desired.y);
+        if speed < MIN_SPEED {
+            return (course, 0.0);
+        }
+        let eta = wrap_angle(course - velocity.x.atan2(velocity.y)).max(-FRAC_PI_2).min(FRAC_PI_2);
+        let distance = desired.norm().max(min).max(MIN_SPEED);
+        let acceleration = 2.0 * speed * speed / distance * eta.sin();
+        let bank = (acceleration / GRAVITY).atan();
+        (course, bank.max(-self.max_bank).min(self.max_bank))
+    }
+
+    /// Positions and velocity as east and north in meter, tracking from `from` to `to`,
+    /// returns desired course and bank angle in radian, both clockwise positive
+    pub fn guide(
+        &self,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+    ) -> (f32, f32) {
+        let l1 = self.distance(velocity.norm());
+        let track = to - from;
+        let length = track.norm();
+        let reference = if length > 0.0 {
+            let unit = track / length;
+            let along = (position - from).dot(&unit);
+            let cross = unit.perp(&(position - from));
+            let ahead = if cross.abs() < l1 { (l1 * l1 - cross * cross).sqrt() } else { 0.0 };
+            if along + ahead >= length {
+                to
+            } else {
+                from + unit * (along + ahead)
+            }
+        } else {
+            to
+        };
+        self.steer(reference, position, velocity, l1)
+    }
+
+    /// Circles clockwise around `center`, reference point is L1 ahead along the circle
+    pub fn loiter(
+        &self,
+        center: Vector2<f32>,
+        radius: f32,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+    ) -> (f32, f32) {
+        let offset = position - center;
+        let distance = offset.norm();
+        let radial = if distance > 0.0 { offset / distance } else { Vector2::new(0.0, 1.0) };
+        let tangent = Vector2::new(radial.y, -radial.x);
+        let angle = (self.distance(velocity.norm()) / radius).min(FRAC_PI_2);
+        let reference = center + (radial * angle.cos() + tangent * angle.sin()) * radius;
+        self.steer(reference, position, velocity, 0.0)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_l1_track_capture() {
+        #[allow(unused_imports)] // false warning
+        use micromath::F32Ext;
+        use nalgebra::Vector2;
+
+        use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
+        use crate::datastructures::measurement::GRAVITY;
+
+        use super::L1;
+
+        let l1 = L1::new(20.0, 0.75, 30.0 / DEGREE_PER_DAG);
+        let (from, to) = (Vector2::new(0.0, 0.0), Vector2::new(0.0, 5000.0));
+
+        // Parallel to track heading north, 100 meter to the east
+        let mut position = Vector2::new(100.0, 0.0);
+        let (speed, mut course) = (20.0f32, 0.0f32);
+        let dt = 1.0 / 50.0;
+        let (desired, bank) = l1.guide(from, to, position, Vector2::new(0.0, speed));
+        assert!(desired < 0.0 && bank < 0.0);
+        for _ in 0..60 * 50 {
+            let velocity = Vector2::new(course.sin() * speed, course.cos() * speed);
+            let (_, bank) = l1.guide(from, to, position, velocity);
+            course += GRAVITY * bank.tan() / speed * dt;
+            position += velocity * dt;
+        }
+        assert!(position.x.abs() < 1.0);
+        assert!((course * DEGREE_PER_DAG).abs() < 1.0);
+
+        // Steerpoint behind, turning back limited by max bank angle
+        let velocity = Vector2::new(0.0, -speed);
+        let (desired, bank) = l1.guide(from, to, Vector2::new(0.0, 1000.0), velocity);
+        assert!((desired * DEGREE_PER_DAG).abs() < 1.0);
+        assert!((bank.abs() * DEGREE_PER_DAG - 30.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_l1_loiter() {
+        #[allow(unused_imports)] // false warning
+        use micromath::F32Ext;
+        use nalgebra::Vector2;
+
+        use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
+        use crate::datastructures::measurement::GRAVITY;
+
+        use super::L1;
+
+        let l1 = L1::new(20.0, 0.75, 45.0 / DEGREE_PER_DAG);
+        let center = Vector2::new(0.0, 0.0);
+
+        // Heading north from 300 meter west of circle center
+        let mut position = Vector2::new(-300.0, 0.0);
+        let (speed, mut course) = (15.0f32, 0.0f32);
+        let dt = 1.0 / 50.0;
+        let mut bank = 0.0;
+        for _ in 0..120 * 50 {
+            let velocity = Vector2::new(course.sin() * speed, course.cos() * speed);
+            bank = l1.loiter(center, 80.0, position, velocity).1;
+            course += GRAVITY * bank.tan() / speed * dt;
+            position += velocity * dt;
+        }
+        assert!((position.norm() - 80.0).abs() < 5.0);
+        assert!(bank > 0.0);
+    }
+}
@@ -0,0 +1,147 @@
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+
+use super::mahony::MagnetismOrHeading;
+
+/// Gradient descent on difference between measured and estimated reference directions,
+/// gradient of each objective with respect to small body rotation is `estimated × measured`,
+/// normalized step is weighted by beta in rad/s against gyro integration
+pub struct Madgwick {
+    sample_interval: f32,
+    beta: f32,
+    magnetic_declination: f32,
+    quaternion: UnitQuaternion<f32>,
+}
+
+impl Madgwick {
+    pub fn new(sample_rate: f32, beta: f32, magnetic_declination: f32) -> Self {
+        Self {
+            sample_interval: 1.0 / sample_rate,
+            beta,
+            magnetic_declination: magnetic_declination.to_radians(),
+            quaternion: UnitQuaternion::new_unchecked(Quaternion::new(1.0, 0.0, 0.0, 0.0)),
+        }
+    }
+
+    pub fn quaternion(&self) -> UnitQuaternion<f32> {
+        self.quaternion
+    }
+
+    fn magnetism_gradient(&self, magnetism_or_heading: MagnetismOrHeading) -> Vector3<f32> {
+        let q = &self.quaternion;
+        let (reference, measured) = match magnetism_or_heading {
+            MagnetismOrHeading::Magnetism(magnetism) => {
+                let measured = match magnetism.try_normalize(0.0) {
+                    Some(normalized) => normalized,
+                    None => return Vector3::new(0.0, 0.0, 0.0),
+                };
+                // Earth field keeps estimated dip, horizontal part points to magnetic north
+                let earth = q.transform_vector(&measured);
+                let horizontal = (earth[0] * earth[0] + earth[1] * earth[1]).sqrt();
+                let declination = self.magnetic_declination;
+                let reference = Vector3::new(
+                    horizontal * declination.sin(),
+                    horizontal * declination.cos(),
+                    earth[2],
+                );
+                (reference, measured)
+            }
+            MagnetismOrHeading::Heading(heading) => {
+                let nose = Vector3::new(0.0, 1.0, 0.0);
+                let vertical = q.transform_vector(&nose)[2];
+                let horizontal = (1.0 - vertical * vertical).max(0.0).sqrt();
+                let rad = heading.to_radians();
+                let reference =
+                    Vector3::new(horizontal * rad.sin(), horizontal * rad.cos(), vertical);
+                (reference, nose)
+            }
+        };
+        q.inverse_transform_vector(&reference).cross(&measured)
+    }
+
+    pub fn update(
+        &mut self,
+        gyro: &Vector3<f32>,
+        acceleration: &Vector3<f32>,
+        magnetism: Option<MagnetismOrHeading>,
+    ) -> Option<UnitQuaternion<f32>> {
+        let acceleration = match acceleration.try_normalize(0.0) {
+            Some(a) => a,
+            None => return None,
+        };
+
+        let q = self.quaternion;
+        let down = q.inverse_transform_vector(&Vector3::new(0.0, 0.0, -1.0));
+        let mut gradient = down.cross(&acceleration);
+        if let Some(magnetism_or_heading) = magnetism {
+            gradient += self.magnetism_gradient(magnetism_or_heading);
+        }
+
+        let mut q_derivate = 0.5 * q.into_inner() * Quaternion::from_parts(0.0, *gyro);
+        if let Some(step) = gradient.try_normalize(0.0) {
+            q_derivate -= q.into_inner() * Quaternion::from_parts(0.0, step) * self.beta;
+        }
+        let q_intergral = q_derivate * self.sample_interval;
+
+        self.quaternion = UnitQuaternion::new_normalize(q.into_inner() + q_intergral);
+        Some(self.quaternion)
+    }
+}
+
+mod test {
+    #[cfg(test)]
+    fn heading(madgwick: &super::Madgwick) -> isize {
+        use crate::datastructures::measurement::euler::Euler;
+
+        let euler: Euler = madgwick.quaternion().into();
+        let yaw = -euler.yaw.to_degrees() as isize;
+        if yaw < 0 {
+            360 + yaw
+        } else {
+            yaw
+        }
+    }
+
+    #[test]
+    fn test_madgwick_course() {
+        use nalgebra::Vector3;
+
+        use crate::algorithm::mahony::MagnetismOrHeading;
+
+        use super::Madgwick;
+
+        let gyro = Vector3::new(0.0, 0.0, 0.0);
+        let accel = Vector3::new(0.0, 0.0, -1.0);
+
+        let mut madgwick = Madgwick::new(100.0, 0.5, 0.0);
+
+        let course: f32 = 270.0;
+        let magnetism = Some(MagnetismOrHeading::Heading(course));
+        for _ in 0..300 {
+            madgwick.update(&gyro, &accel, magnetism);
+        }
+        assert!((heading(&madgwick) - 270).abs() <= 1);
+    }
+
+    #[test]
+    fn test_madgwick_magnetism() {
+        use nalgebra::Vector3;
+
+        use crate::algorithm::mahony::MagnetismOrHeading;
+
+        use super::Madgwick;
+
+        let gyro = Vector3::new(0.0, 0.0, 0.0);
+        let accel = Vector3::new(0.0, 0.0, -1.0);
+
+        let mut madgwick = Madgwick::new(100.0, 0.5, 0.0);
+
+        // Heading east, magnetic north on the left with downward dip
+        let magnetism = Some(MagnetismOrHeading::Magnetism(Vector3::new(-0.4, 0.0, -0.9)));
+        for _ in 0..300 {
+            madgwick.update(&gyro, &accel, magnetism);
+        }
+        assert!((heading(&madgwick) - 90).abs() <= 1);
+    }
+}
@@ -1,8 +1,38 @@
+pub mod ekf;
+pub mod ins;
+pub mod l1;
 pub mod lpf;
+pub mod madgwick;
 pub mod mahony;
+pub mod pid;
+pub mod tecs;
 
+use core::f32::consts::PI;
 use core::ops::{Add, AddAssign, Div, Mul, Sub};
 
+/// Wraps angle difference in radian into [-π, π]
+pub fn wrap_angle(angle: f32) -> f32 {
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle < -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// Rotation about unit axis in degree, for tests
+#[cfg(test)]
+pub fn rotation(axis: nalgebra::Vector3<f32>, degree: f32) -> nalgebra::UnitQuaternion<f32> {
+    #[allow(unused_imports)] // false warning
+    use micromath::F32Ext;
+    use nalgebra::{Quaternion, UnitQuaternion};
+
+    let half = degree.to_radians() / 2.0;
+    let (sin, cos) = (half.sin(), half.cos());
+    UnitQuaternion::new_normalize(Quaternion::from_parts(cos, axis * sin))
+}
+
 #[derive(Copy)]
 pub struct ComplementaryFilter<T: Default> {
     alpha: T,
@@ -0,0 +1,121 @@
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PID {
+    kp: f32,
+    ki: f32,
+    kd: f32,
+    integral_limit: f32,
+    output_limit: f32,
+    anti_windup: bool,
+
+    integral: f32,
+    last_error: Option<f32>,
+}
+
+fn limit(value: f32, limit: f32) -> f32 {
+    if value > limit {
+        limit
+    } else if value < -limit {
+        -limit
+    } else {
+        value
+    }
+}
+
+impl PID {
+    pub fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: f32::INFINITY,
+            output_limit: f32::INFINITY,
+            anti_windup: false,
+            integral: 0.0,
+            last_error: None,
+        }
+    }
+
+    pub fn set_integral_limit(&mut self, limit: f32) {
+        self.integral_limit = limit;
+    }
+
+    pub fn set_output_limit(&mut self, limit: f32) {
+        self.output_limit = limit;
+    }
+
+    /// Stop integrating while output saturated in the same direction as error
+    pub fn set_anti_windup(&mut self, enable: bool) {
+        self.anti_windup = enable;
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last_error = None;
+    }
+
+    pub fn update(&mut self, error: f32, dt: f32) -> f32 {
+        let derivative = match self.last_error {
+            Some(last_error) if dt > 0.0 => (error - last_error) / dt,
+            _ => 0.0,
+        };
+        self.last_error = Some(error);
+
+        let integral = limit(self.integral + self.ki * error * dt, self.integral_limit);
+        let output = self.kp * error + integral + self.kd * derivative;
+        let saturated = output > self.output_limit || output < -self.output_limit;
+        if !(self.anti_windup && saturated && output * error > 0.0) {
+            self.integral = integral;
+        }
+        limit(self.kp * error + self.integral + self.kd * derivative, self.output_limit)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_pid_proportional() {
+        use super::PID;
+
+        let mut pid = PID::new(2.0, 0.0, 0.0);
+        assert_eq!(pid.update(0.5, 0.02), 1.0);
+        pid.set_output_limit(0.5);
+        assert_eq!(pid.update(0.5, 0.02), 0.5);
+        assert_eq!(pid.update(-0.5, 0.02), -0.5);
+    }
+
+    #[test]
+    fn test_pid_integral_limit() {
+        use super::PID;
+
+        let mut pid = PID::new(0.0, 1.0, 0.0);
+        pid.set_integral_limit(0.1);
+        for _ in 0..100 {
+            pid.update(1.0, 0.02);
+        }
+        assert_eq!(pid.update(1.0, 0.02), 0.1);
+        pid.reset();
+        assert_eq!(pid.update(0.0, 0.02), 0.0);
+    }
+
+    #[test]
+    fn test_pid_anti_windup() {
+        use super::PID;
+
+        let mut pid = PID::new(1.0, 1.0, 0.0);
+        pid.set_output_limit(1.0);
+        pid.set_anti_windup(true);
+        for _ in 0..100 {
+            assert_eq!(pid.update(2.0, 0.02), 1.0);
+        }
+        // integral not wound up, so output follows error immediately
+        assert!((pid.update(-0.5, 0.02) + 0.51).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_pid_derivative() {
+        use super::PID;
+
+        let mut pid = PID::new(0.0, 0.0, 0.1);
+        assert_eq!(pid.update(1.0, 0.1), 0.0);
+        assert!((pid.update(2.0, 0.1) - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::algorithm::lpf::LPF;
+use crate::datastructures::measurement::GRAVITY;
+
+// Rate error feedback relative to rate demand
+const DAMPING: f32 = 0.5;
+
+fn limit(value: f32, min: f32, max: f32) -> f32 {
+    value.max(min).min(max)
+}
+
+/// Total energy control, throttle controls total specific energy
+/// while pitch trades potential energy against kinetic energy
+///
+/// Specific potential energy is g·h and specific kinetic energy is ½V²,
+/// energy errors are turned into rate demands by time constants
+pub struct TECS {
+    interval: f32,
+    time_constant: f32,
+    throttle_time_constant: f32,
+    integrator_gain: f32,
+    max_climb_rate: f32,
+    max_sink_rate: f32,
+    max_pitch: f32,
+    trim_throttle: f32,
+    acceleration: LPF<f32>,
+    speed: Option<f32>,
+    throttle_integral: f32,
+    pitch_integral: f32,
+}
+
+impl TECS {
+    pub fn new(
+        sample_rate: f32,
+        time_constant: f32,
+        throttle_time_constant: f32,
+        integrator_gain: f32,
+    ) -> Self {
+        Self {
+            interval: 1.0 / sample_rate,
+            time_constant,
+            throttle_time_constant,
+            integrator_gain,
+            max_climb_rate: 5.0,
+            max_sink_rate: 5.0,
+            max_pitch: 0.35,
+            trim_throttle: 0.5,
+            acceleration: LPF::<f32>::new(sample_rate, 1.0),
+            speed: None,
+            throttle_integral: 0.0,
+            pitch_integral: 0.0,
+        }
+    }
+
+    /// Climb and sink rate in meter per second achievable at full and idle throttle
+    pub fn set_climb_limits(&mut self, max_climb_rate: f32, max_sink_rate: f32) {
+        self.max_climb_rate = max_climb_rate;
+        self.max_sink_rate = max_sink_rate;
+    }
+
+    /// Pitch limit in radian
+    pub fn set_pitch_limit(&mut self, max_pitch: f32) {
+        self.max_pitch = max_pitch;
+    }
+
+    /// Throttle in [0, 1] for level flight at cruise speed
+    pub fn set_trim_throttle(&mut self, trim_throttle: f32) {
+        self.trim_throttle = trim_throttle;
+    }
+
+    pub fn reset(&mut self) {
+        self.speed = None;
+        self.throttle_integral = 0.0;
+        self.pitch_integral = 0.0;
+    }
+
+    /// Altitude in meter, climb rate and speed in meter per second,
+    /// returns throttle in [0, 1] and pitch in radian with positive nose up
+    pub fn update(
+        &mut self,
+        altitude: f32,
+        climb_rate: f32,
+        speed: f32,
+        target_altitude: f32,
+        target_speed: f32,
+    ) -> (f32, f32) {
+        let dt = self.interval;
+        let acceleration = match self.speed {
+            Some(last) => self.acceleration.filter((speed - last) / dt),
+            None => 0.0,
+        };
+        self.speed = Some(speed);
+        let speed = speed.max(1.0);
+
+        let max_energy_rate = GRAVITY * self.max_climb_rate;
+        let min_energy_rate = -GRAVITY * self.max_sink_rate;
+        let potential_error = GRAVITY * (target_altitude - altitude);
+        let kinetic_error = (target_speed * target_speed - speed * speed) / 2.0;
+        let potential_rate = GRAVITY * climb_rate;
+        let kinetic_rate = speed * acceleration;
+
+        let total_error = potential_error + kinetic_error;
+        let total_rate_demand = total_error / self.throttle_time_constant;
+        let total_rate_demand = limit(total_rate_demand, min_energy_rate, max_energy_rate);
+        let total_rate_error = total_rate_demand - (potential_rate + kinetic_rate);
+        let gain = 1.0 / (max_energy_rate - min_energy_rate);
+        let integral = self.throttle_integral + self.integrator_gain * total_rate_error * gain * dt;
+        let min_integral = -self.trim_throttle;
+        self.throttle_integral = limit(integral, min_integral, 1.0 - self.trim_throttle);
+        let throttle = total_rate_demand + DAMPING * total_rate_error;
+        let throttle = self.trim_throttle + throttle * gain + self.throttle_integral;
+
+        let balance_rate_demand = (potential_error - kinetic_error) / self.time_constant;
+        let balance_rate_error = balance_rate_demand - (potential_rate - kinetic_rate);
+        let integral = self.pitch_integral + self.integrator_gain * balance_rate_error * dt;
+        let max_integral = self.max_pitch * GRAVITY * speed;
+        self.pitch_integral = limit(integral, -max_integral, max_integral);
+        let pitch = balance_rate_demand + DAMPING * balance_rate_error + self.pitch_integral;
+        let pitch = pitch / (2.0 * GRAVITY * speed);
+
+        (limit(throttle, 0.0, 1.0), limit(pitch, -self.max_pitch, self.max_pitch))
+    }
+}
+
+mod test {
+    #[cfg(test)]
+    struct PointMass {
+        altitude: f32,
+        speed: f32,
+        flight_path: f32,
+    }
+
+    #[cfg(test)]
+    impl PointMass {
+        // Full throttle accelerates by 9 m/s², drag balances half throttle at 15 m/s,
+        // flight path follows pitch with 0.5s time constant
+        fn step(&mut self, throttle: f32, pitch: f32, dt: f32) -> f32 {
+            #[allow(unused_imports)] // false warning
+            use micromath::F32Ext;
+
+            use crate::datastructures::measurement::GRAVITY;
+
+            let drag = 0.02 * self.speed * self.speed;
+            let gravity = GRAVITY * self.flight_path.sin();
+            self.speed += (9.0 * throttle - drag - gravity) * dt;
+            self.flight_path += (pitch - self.flight_path) / 0.5 * dt;
+            let climb_rate = self.speed * self.flight_path.sin();
+            self.altitude += climb_rate * dt;
+            climb_rate
+        }
+    }
+
+    #[test]
+    fn test_tecs_point_mass() {
+        use super::TECS;
+
+        let mut tecs = TECS::new(50.0, 5.0, 2.0, 0.1);
+        let mut plane = PointMass { altitude: 100.0, speed: 15.0, flight_path: 0.0 };
+        let mut climb_rate = 0.0;
+
+        // Climb and speed up together
+        for _ in 0..60 * 50 {
+            let (throttle, pitch) =
+                tecs.update(plane.altitude, climb_rate, plane.speed, 150.0, 18.0);
+            assert!(throttle >= 0.0 && throttle <= 1.0);
+            assert!(pitch.abs() <= 0.35);
+            climb_rate = plane.step(throttle, pitch, 1.0 / 50.0);
+        }
+        assert!((plane.altitude - 150.0).abs() < 1.0);
+        assert!((plane.speed - 18.0).abs() < 0.5);
+
+        // Slow down at constant altitude
+        for _ in 0..60 * 50 {
+            let (throttle, pitch) =
+                tecs.update(plane.altitude, climb_rate, plane.speed, 150.0, 14.0);
+            climb_rate = plane.step(throttle, pitch, 1.0 / 50.0);
+            assert!((plane.altitude - 150.0).abs() < 5.0);
+        }
+        assert!((plane.altitude - 150.0).abs() < 1.0);
+        assert!((plane.speed - 14.0).abs() < 0.5);
+    }
+}
@@ -0,0 +1,270 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::datastructures::arming::{Arming, PreArmCheck, PreArmFailures};
+use crate::datastructures::calibration::EscCalibration;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{AgingStaticData, DataWriter, StaticData};
+use crate::datastructures::input::{ControlInput, InputType};
+use crate::datastructures::measurement::battery::Battery;
+use crate::datastructures::GNSSFixed;
+
+// 5% of throttle range
+const LOW_THROTTLE: i16 = i16::MIN + (u16::MAX / 20) as i16;
+
+struct Receiver {
+    input: Box<dyn AgingStaticData<ControlInput>>,
+    switch: Box<dyn AgingStaticData<bool>>,
+}
+
+struct GNSS {
+    fixed: Box<dyn StaticData<GNSSFixed>>,
+    mission: Box<dyn StaticData<u8>>,
+}
+
+pub struct ArmingControl<B, C> {
+    battery: B,
+    calibrated: C,
+    receiver: Option<Receiver>,
+    gnss: Option<GNSS>,
+    esc_calibration: Option<Box<dyn StaticData<EscCalibration>>>,
+
+    battery_cells: u8,
+    switch: bool,
+    armed: bool,
+    output: Rc<SingularData<Arming>>,
+}
+
+impl<B, C> ArmingControl<B, C> {
+    pub fn new(battery: B, calibrated: C) -> Self {
+        Self {
+            battery,
+            calibrated,
+            receiver: None,
+            gnss: None,
+            esc_calibration: None,
+
+            battery_cells: config::get().battery.cells,
+            switch: true, // Switch on while booting is not an arm request
+            armed: false,
+            output: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn set_receiver(
+        &mut self,
+        input: Box<dyn AgingStaticData<ControlInput>>,
+        switch: Box<dyn AgingStaticData<bool>>,
+    ) {
+        self.receiver = Some(Receiver { input, switch })
+    }
+
+    pub fn set_gnss(
+        &mut self,
+        fixed: Box<dyn StaticData<GNSSFixed>>,
+        mission: Box<dyn StaticData<u8>>,
+    ) {
+        self.gnss = Some(GNSS { fixed, mission })
+    }
+
+    pub fn set_esc_calibration(&mut self, calibration: Box<dyn StaticData<EscCalibration>>) {
+        self.esc_calibration = Some(calibration);
+    }
+
+    pub fn reader(&self) -> SingularDataSource<Arming> {
+        SingularDataSource::new(&self.output)
+    }
+
+    /// Switch is none while receiver stale, which keeps armed state and leaves outputs to failsafe
+    pub fn update(&mut self, switch: Option<bool>, failures: PreArmFailures) -> bool {
+        let switch = match switch {
+            Some(switch) => switch,
+            None => return self.armed,
+        };
+        let request = switch && !self.switch;
+        self.switch = switch;
+        if !switch {
+            if self.armed {
+                info!("Disarmed");
+            }
+            self.armed = false;
+        } else if request && !self.armed {
+            if failures.is_empty() {
+                info!("Armed");
+                self.armed = true;
+            } else {
+                warn!("Arming refused: {}", Arming { armed: false, failures });
+            }
+        }
+        self.armed
+    }
+}
+
+impl<B: StaticData<Battery>, C: StaticData<bool>> ArmingControl<B, C> {
+    fn check(&mut self, rate: Rate) -> (Option<bool>, PreArmFailures) {
+        let mut failures = PreArmFailures::default();
+        if !self.calibrated.read() {
+            failures.insert(PreArmCheck::GyroNotCalibrated);
+        }
+        if !config::loaded() {
+            failures.insert(PreArmCheck::ConfigNotLoaded);
+        }
+
+        let mut switch = None;
+        let arm_switch = config::get().receiver.inputs.0.contains_key(&InputType::Arm);
+        if !arm_switch {
+            failures.insert(PreArmCheck::NoArmSwitch);
+        }
+        match self.receiver.as_mut() {
+            Some(receiver) => match (receiver.input.read(rate), receiver.switch.read(rate)) {
+                (Some(input), value) => {
+                    if input.throttle > LOW_THROTTLE {
+                        failures.insert(PreArmCheck::ThrottleNotLow);
+                    }
+                    match value {
+                        Some(value) => switch = Some(value),
+                        None if arm_switch => failures.insert(PreArmCheck::ReceiverStale),
+                        None => (),
+                    }
+                }
+                _ => failures.insert(PreArmCheck::ReceiverStale),
+            },
+            None => failures.insert(PreArmCheck::ReceiverStale),
+        }
+
+        let battery = self.battery.read();
+        if self.battery_cells == 0 {
+            self.battery_cells = battery.cells().unwrap_or(0);
+        }
+        let warning: f32 = config::get().battery.warning_cell_voltage.into();
+        let cells = core::cmp::max(self.battery_cells, 1) as u16;
+        if self.battery_cells == 0 || ((battery.0 / cells) as f32) < warning * 1000.0 {
+            failures.insert(PreArmCheck::LowBattery);
+        }
+
+        if let Some(ref mut gnss) = self.gnss {
+            if gnss.mission.read() > 0 && !gnss.fixed.read().0 {
+                failures.insert(PreArmCheck::NoGNSSFix);
+            }
+        }
+
+        if let Some(ref mut calibration) = self.esc_calibration {
+            if calibration.read() != EscCalibration::Idle {
+                failures.insert(PreArmCheck::EscCalibrating);
+            }
+        }
+        (switch, failures)
+    }
+}
+
+impl<B: StaticData<Battery>, C: StaticData<bool>> Schedulable for ArmingControl<B, C> {
+    fn rate(&self) -> Rate {
+        50
+    }
+
+    fn schedule(&mut self) -> bool {
+        let (switch, failures) = self.check(self.rate());
+        let armed = self.update(switch, failures);
+        self.output.write(Arming { armed, failures });
+        true
+    }
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_arming() {
+        use crate::components::logger::{self, Level};
+        use crate::config::{self, Config};
+        use crate::datastructures::arming::{PreArmCheck, PreArmFailures};
+
+        use super::ArmingControl;
+
+        logger::init(Level::Debug);
+        config::replace(Config::default());
+        let mut arming = ArmingControl::new((), ());
+        let ok = PreArmFailures::default();
+        let mut failures = PreArmFailures::default();
+        failures.insert(PreArmCheck::LowBattery);
+
+        // switch already on at boot
+        assert_eq!(arming.update(Some(true), ok), false);
+        assert_eq!(arming.update(Some(false), ok), false);
+        assert_eq!(arming.update(Some(true), failures), false);
+        // failures cleared but no new switch edge
+        assert_eq!(arming.update(Some(true), ok), false);
+        assert_eq!(arming.update(Some(false), ok), false);
+        assert_eq!(arming.update(Some(true), ok), true);
+        // failures while armed won't disarm
+        assert_eq!(arming.update(Some(true), failures), true);
+        assert_eq!(arming.update(Some(false), failures), false);
+
+        // receiver stale blocks arming but won't disarm
+        let mut stale = PreArmFailures::default();
+        stale.insert(PreArmCheck::ReceiverStale);
+        assert_eq!(arming.update(None, stale), false);
+        assert_eq!(arming.update(Some(true), ok), true);
+        assert_eq!(arming.update(None, stale), true);
+        assert_eq!(arming.update(Some(true), ok), true);
+        assert_eq!(arming.update(Some(false), ok), false);
+    }
+
+    #[test]
+    #[serial]
+    fn test_pre_arm_checks() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use crate::config::receiver::Input;
+        use crate::config::{self, Config};
+        use crate::datastructures::arming::PreArmCheck;
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::DataWriter;
+        use crate::datastructures::input::{ControlInput, InputType};
+        use crate::datastructures::measurement::battery::Battery;
+
+        use super::ArmingControl;
+
+        config::replace(Config::default());
+        let battery = Rc::new(SingularData::default());
+        let calibrated = Rc::new(SingularData::default());
+        calibrated.write(true);
+        let receiver = Rc::new(SingularData::default());
+        let switch = Rc::new(SingularData::default());
+        let mut arming = ArmingControl::new(
+            SingularDataSource::new(&battery),
+            SingularDataSource::new(&calibrated),
+        );
+        arming.set_receiver(
+            Box::new(SingularDataSource::new(&receiver)),
+            Box::new(SingularDataSource::new(&switch)),
+        );
+
+        // Missing arm switch is not reported as receiver stale
+        receiver.write(ControlInput { throttle: i16::MIN, ..Default::default() });
+        let (_, failures) = arming.check(50);
+        assert!(failures.contains(PreArmCheck::NoArmSwitch));
+        assert!(!failures.contains(PreArmCheck::ReceiverStale));
+
+        // Battery not sampled yet at boot won't fix cell count
+        assert!(failures.contains(PreArmCheck::LowBattery));
+        battery.write(Battery(3 * 3400));
+        let (_, failures) = arming.check(50);
+        assert!(failures.contains(PreArmCheck::LowBattery));
+        battery.write(Battery(3 * 3900));
+        let (_, failures) = arming.check(50);
+        assert!(!failures.contains(PreArmCheck::LowBattery));
+
+        let mut config = Config::default();
+        config.receiver.inputs.0.insert(InputType::Arm, Input::new(4)).ok();
+        config::replace(config);
+        receiver.write(ControlInput { throttle: i16::MIN, ..Default::default() });
+        switch.write(true);
+        let (switch_value, failures) = arming.check(50);
+        assert_eq!(switch_value, Some(true));
+        assert!(!failures.contains(PreArmCheck::ReceiverStale));
+        assert!(!failures.contains(PreArmCheck::NoArmSwitch));
+    }
+}
@@ -4,10 +4,13 @@ use ascii_osd_hud::hud::HUD;
 use ascii_osd_hud::symbol::default_symbol_table;
 use ascii_osd_hud::telemetry::{Notes, Steerpoint, Telemetry, Unit};
 use ascii_osd_hud::{AspectRatio, PixelRatio};
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
 
 use crate::components::telemetry::TelemetryData;
 use crate::datastructures::coordinate::SphericalCoordinate;
 use crate::datastructures::data_source::StaticData;
+use crate::datastructures::flight_mode::FlightMode;
 use crate::datastructures::measurement::displacement::DistanceVector;
 use crate::datastructures::measurement::unit::{Feet, Knot, Meter, NauticalMile};
 use crate::datastructures::measurement::VelocityVector;
@@ -18,6 +21,21 @@ type Screen = [[u8; 29]; 15];
 pub type ScreenConsumer = fn(&Screen);
 
 const NO_GPS: &str = "NO GPS";
+const NO_NAV: &str = "NO NAV";
+
+// Appends estimated time enroute as " mm:ss" after steerpoint name
+fn with_eta<'a>(buffer: &'a mut [u8; 16], name: &str, distance: f32, speed: f32) -> &'a str {
+    let mut index = name.len().min(buffer.len() - 6);
+    buffer[..index].copy_from_slice(&name.as_bytes()[..index]);
+    let seconds = if speed > 1.0 { (distance / speed) as u32 } else { u32::MAX };
+    let seconds = seconds.min(99 * 60 + 59);
+    let (minutes, seconds) = ((seconds / 60) as u8, (seconds % 60) as u8);
+    for &byte in [b' ', minutes / 10, minutes % 10, b':', seconds / 10, seconds % 10].iter() {
+        buffer[index] = if byte < 10 { b'0' + byte } else { byte };
+        index += 1;
+    }
+    unsafe { core::str::from_utf8_unchecked(&buffer[..index]) }
+}
 
 pub struct AsciiHud<T> {
     hud: HUD,
@@ -54,10 +72,18 @@ impl<T: StaticData<TelemetryData>> AsciiHud<T> {
         let vector = misc.quaternion.inverse_transform_vector(&delta.into());
         let transformed: DistanceVector<f32, Meter> = vector.into();
         let coordinate: SphericalCoordinate<Meter> = (transformed * 10.0).into();
+        let mut name_buffer = [0u8; 16];
+        let mut name = nav.steerpoint.waypoint.name;
+        if status.flight_mode == FlightMode::ReturnToHome {
+            let (x, y) = (delta.x.value(), delta.y.value());
+            let (vx, vy) = (nav.speed_vector.x.value(), nav.speed_vector.y.value());
+            let distance = (x * x + y * y).sqrt();
+            name = with_eta(&mut name_buffer, name, distance, (vx * vx + vy * vy).sqrt());
+        }
         let steerpoint = Steerpoint {
             number: nav.steerpoint.index,
-            name: nav.steerpoint.waypoint.name,
-            heading: delta.azimuth(),
+            name,
+            heading: nav.guidance.course,
             coordinate: coordinate.to_unit(NauticalMile).into(),
         };
 
@@ -74,13 +100,52 @@ impl<T: StaticData<TelemetryData>> AsciiHud<T> {
 
         let mut note_buffer = [0u8; 30];
         let mut index = 0;
-        if let Some(gnss) = sensor.gnss {
-            if !gnss.fixed {
-                note_buffer[index..index + NO_GPS.len()].copy_from_slice(NO_GPS.as_bytes());
-                index += NO_GPS.len();
+        let failsafe = status.failsafe.short_name();
+        if failsafe.len() > 0 {
+            note_buffer[..failsafe.len()].copy_from_slice(failsafe.as_bytes());
+            index += failsafe.len();
+        }
+        let fence = status.geofence.short_name();
+        if fence.len() > 0 {
+            if index > 0 {
+                note_buffer[index] = b' ';
+                index += 1;
             }
+            note_buffer[index..index + fence.len()].copy_from_slice(fence.as_bytes());
+            index += fence.len();
+        }
+        let navigation = match sensor.gnss {
+            Some(gnss) if !gnss.fixed => NO_GPS,
+            Some(_) if !status.ins.horizontal => NO_NAV,
+            _ => "",
+        };
+        if navigation.len() > 0 {
+            if index > 0 {
+                note_buffer[index] = b' ';
+                index += 1;
+            }
+            note_buffer[index..index + navigation.len()].copy_from_slice(navigation.as_bytes());
+            index += navigation.len();
         }
         let note_left = unsafe { core::str::from_utf8_unchecked(&note_buffer[..index]) };
+
+        let mut note_buffer = [0u8; 30];
+        let mut index = 0;
+        if !status.arming.armed {
+            for check in status.arming.failures.iter() {
+                let name = check.short_name();
+                if index + name.len() + 1 > note_buffer.len() {
+                    break;
+                }
+                if index > 0 {
+                    note_buffer[index] = b' ';
+                    index += 1;
+                }
+                note_buffer[index..index + name.len()].copy_from_slice(name.as_bytes());
+                index += name.len();
+            }
+        }
+        let note_right = unsafe { core::str::from_utf8_unchecked(&note_buffer[..index]) };
         let hud_telemetry = Telemetry {
             altitude: altitude.value() as i16,
             aoa: aoa as i8,
@@ -89,7 +154,11 @@ impl<T: StaticData<TelemetryData>> AsciiHud<T> {
             heading: status.heading,
             g_force: status.g_force,
             height: if height > 200 { i16::MIN } else { height },
-            notes: Notes { left: note_left, center: "", right: "" },
+            notes: Notes {
+                left: note_left,
+                center: status.flight_mode.short_name(),
+                right: note_right,
+            },
             rssi: status.rssi as u8,
             unit: Unit::Aviation,
             speed_vector: speed_vector.into(),
@@ -102,6 +171,15 @@ impl<T: StaticData<TelemetryData>> AsciiHud<T> {
 }
 
 mod test {
+    #[test]
+    fn test_eta() {
+        use super::with_eta;
+
+        let mut buffer = [0u8; 16];
+        assert_eq!(with_eta(&mut buffer, "HOME", 1500.0, 20.0), "HOME 01:15");
+        assert_eq!(with_eta(&mut buffer, "HOME", 1500.0, 0.0), "HOME 99:59");
+    }
+
     #[test]
     fn test_speed_vector() {
         use ascii_osd_hud::telemetry as hud;
@@ -0,0 +1,638 @@
+use alloc::boxed::Box;
+use core::time::Duration;
+
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::algorithm::pid::PID;
+use crate::algorithm::tecs::TECS;
+use crate::algorithm::wrap_angle;
+use crate::config;
+use crate::config::autopilot::{RthAction, TECS as TECSConfig};
+use crate::config::stabilizer::PID as PIDConfig;
+use crate::datastructures::coordinate::Position;
+use crate::datastructures::data_source::StaticData;
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::input::{AuxiliaryInput, ControlInput, InputType};
+use crate::datastructures::ins::Validity;
+use crate::datastructures::measurement::euler::{Euler, DEGREE_PER_DAG};
+use crate::datastructures::measurement::unit::Meter;
+use crate::datastructures::measurement::{Altitude, VelocityVector};
+use crate::datastructures::waypoint::{Guidance, Steerpoint};
+
+// Stick deflection beyond 10% overrides autopilot
+const OVERRIDE_THRESHOLD: i16 = i16::MAX / 10;
+
+fn to_pid(config: &PIDConfig, output_limit: f32) -> PID {
+    let mut pid = PID::new(config.kp.into(), config.ki.into(), config.kd.into());
+    pid.set_integral_limit(config.i_limit.into());
+    pid.set_output_limit(output_limit);
+    pid.set_anti_windup(true);
+    pid
+}
+
+fn to_tecs(config: &TECSConfig, max_climb_rate: u8, sample_rate: u16) -> TECS {
+    let time_constant = config.time_constant as f32;
+    let throttle_time_constant = config.throttle_time_constant as f32;
+    let integrator_gain = config.integrator_gain.into();
+    let mut tecs =
+        TECS::new(sample_rate as f32, time_constant, throttle_time_constant, integrator_gain);
+    tecs.set_climb_limits(max_climb_rate as f32, config.max_sink_rate as f32);
+    tecs.set_pitch_limit(config.max_pitch as f32 / DEGREE_PER_DAG);
+    tecs.set_trim_throttle(config.trim_throttle as f32 / 100.0);
+    tecs
+}
+
+fn to_i16(value: f32) -> i16 {
+    (value * i16::MAX as f32) as i16
+}
+
+fn to_throttle(value: f32) -> i16 {
+    (i16::MIN as f32 + value * u16::MAX as f32) as i16
+}
+
+/// Altitude in meter, climb rate and speed in meter per second,
+/// heading, roll and pitch in radian with clockwise, right and nose up positive
+#[derive(Copy, Clone, Default, Debug)]
+pub struct State {
+    pub altitude: f32,
+    pub climb_rate: f32,
+    pub heading: f32,
+    pub roll: f32,
+    pub pitch: f32,
+    pub speed: f32,
+}
+
+/// Height above home and horizontal distance to home in meter,
+/// desired course and bank angle from guidance in radian
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Home {
+    pub height: f32,
+    pub distance: f32,
+    pub course: f32,
+    pub bank: f32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Return {
+    height: f32,
+    climbed: bool,
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Hold {
+    pub altitude: bool,
+    pub heading: bool,
+}
+
+/// Holds altitude and heading captured when engaged, corrections are added to stick input,
+/// stick deflection takes over and recaptures target when released.
+///
+/// Fixed-wing holds altitude with pitch and heading with roll,
+/// multirotor holds altitude with throttle based on hover throttle and heading with yaw
+///
+/// Return-to-home takes over all axes, climbs to minimum altitude before turning home,
+/// fixed-wing follows guidance bank angle with speed and altitude controlled by TECS.
+/// Loiter holds altitude where engaged, fixed-wing circles by guidance bank angle
+/// while multirotor levels, multirotor climb rate control is based on hover throttle
+pub struct Autopilot {
+    altimeter: Box<dyn StaticData<Altitude>>,
+    speedometer: Box<dyn StaticData<VelocityVector<f32, Meter>>>,
+    attitude: Box<dyn StaticData<UnitQuaternion<f32>>>,
+    flight_mode: Option<Box<dyn StaticData<FlightMode>>>,
+    navigation: Option<Box<dyn StaticData<(Position, Steerpoint)>>>,
+    guidance: Option<Box<dyn StaticData<Guidance>>>,
+    validity: Option<Box<dyn StaticData<Validity>>>,
+    multirotor: bool,
+    attitude_gain: f32,
+    multirotor_tilt: f32,
+    altitude: PID,
+    climb_rate: PID,
+    heading: PID,
+    tecs: TECS,
+    target_altitude: Option<f32>,
+    target_heading: Option<f32>,
+    rth: Option<Return>,
+    loitering: bool,
+    time: Option<Duration>,
+}
+
+impl Autopilot {
+    pub fn new(
+        altimeter: Box<dyn StaticData<Altitude>>,
+        speedometer: Box<dyn StaticData<VelocityVector<f32, Meter>>>,
+        attitude: Box<dyn StaticData<UnitQuaternion<f32>>>,
+        sample_rate: u16,
+    ) -> Self {
+        let config = config::get();
+        let autopilot = &config.autopilot;
+        Self {
+            altimeter,
+            speedometer,
+            attitude,
+            flight_mode: None,
+            navigation: None,
+            guidance: None,
+            validity: None,
+            multirotor: config.aircraft.configuration.is_multirotor(),
+            attitude_gain: autopilot.attitude_gain.into(),
+            multirotor_tilt: autopilot.multirotor_tilt as f32 / DEGREE_PER_DAG,
+            altitude: to_pid(&autopilot.altitude, autopilot.max_climb_rate as f32),
+            climb_rate: to_pid(&autopilot.climb_rate, 1.0),
+            heading: to_pid(&autopilot.heading, 1.0),
+            tecs: to_tecs(&autopilot.tecs, autopilot.max_climb_rate, sample_rate),
+            target_altitude: None,
+            target_heading: None,
+            rth: None,
+            loitering: false,
+            time: None,
+        }
+    }
+
+    pub fn set_flight_mode(&mut self, flight_mode: Box<dyn StaticData<FlightMode>>) {
+        self.flight_mode = Some(flight_mode);
+    }
+
+    /// Required by return-to-home and loiter, steerpoint is expected to be home while returning,
+    /// both are unavailable until INS horizontal position is valid
+    pub fn set_navigation(
+        &mut self,
+        navigation: Box<dyn StaticData<(Position, Steerpoint)>>,
+        guidance: Box<dyn StaticData<Guidance>>,
+        validity: Box<dyn StaticData<Validity>>,
+    ) {
+        self.navigation = Some(navigation);
+        self.guidance = Some(guidance);
+        self.validity = Some(validity);
+    }
+
+    fn hold(&self, mode: FlightMode, auxiliary: &AuxiliaryInput) -> Hold {
+        let engaged = match config::get().autopilot.engage {
+            Some(InputType::Aux(index)) => auxiliary.aux[index as usize - 1] > 0,
+            _ => false,
+        };
+        Hold {
+            altitude: engaged || mode == FlightMode::AltitudeHold,
+            heading: engaged || mode == FlightMode::HeadingHold,
+        }
+    }
+
+    fn to_attitude_input(&self, desired: f32, actual: f32) -> i16 {
+        to_i16(((desired - actual) * self.attitude_gain).max(-1.0).min(1.0))
+    }
+
+    fn hold_altitude(&mut self, state: &State, dt: f32) -> f32 {
+        let target = *self.target_altitude.get_or_insert(state.altitude);
+        let climb_rate = self.altitude.update(target - state.altitude, dt);
+        self.climb_rate.update(climb_rate - state.climb_rate, dt)
+    }
+
+    fn release_altitude(&mut self) {
+        self.target_altitude = None;
+        self.altitude.reset();
+        self.climb_rate.reset();
+    }
+
+    fn hold_heading(&mut self, state: &State, dt: f32) -> f32 {
+        let target = *self.target_heading.get_or_insert(state.heading);
+        self.heading.update(wrap_angle(target - state.heading), dt)
+    }
+
+    fn release_heading(&mut self) {
+        self.target_heading = None;
+        self.heading.reset();
+    }
+
+    pub fn control(
+        &mut self,
+        hold: Hold,
+        mut input: ControlInput,
+        state: &State,
+        dt: f32,
+    ) -> ControlInput {
+        let (vertical, lateral) = match self.multirotor {
+            true => (&mut input.throttle, &mut input.yaw),
+            false => (&mut input.pitch, &mut input.roll),
+        };
+        if hold.altitude && vertical.saturating_abs() <= OVERRIDE_THRESHOLD {
+            let base = match self.multirotor {
+                true => to_throttle(config::get().autopilot.hover_throttle as f32 / 100.0),
+                false => *vertical,
+            };
+            *vertical = base.saturating_add(to_i16(self.hold_altitude(state, dt)));
+        } else {
+            self.release_altitude();
+        }
+        if hold.heading && lateral.saturating_abs() <= OVERRIDE_THRESHOLD {
+            *lateral = lateral.saturating_add(to_i16(self.hold_heading(state, dt)));
+        } else {
+            self.release_heading();
+        }
+        input
+    }
+
+    pub fn return_to_home(
+        &mut self,
+        mut input: ControlInput,
+        state: &State,
+        home: &Home,
+        dt: f32,
+    ) -> ControlInput {
+        let config = &config::get().autopilot;
+        let min_altitude = config.rth.min_altitude as f32;
+        let rth = self.rth.get_or_insert(Return { height: home.height, climbed: false });
+        rth.height = rth.height.max(min_altitude);
+        rth.climbed |= home.height >= min_altitude;
+        let (target, climbed) = (rth.height, rth.climbed);
+        let landing_speed = config.rth.landing_speed as f32;
+        let loitering = home.distance <= config.rth.loiter_radius as f32;
+        let landing = loitering && config.rth.action == RthAction::Land;
+
+        if self.multirotor {
+            let climb_rate = match landing {
+                true => -landing_speed,
+                false => self.altitude.update(target - home.height, dt),
+            };
+            let throttle = self.climb_rate.update(climb_rate - state.climb_rate, dt);
+            let hover = to_throttle(config.hover_throttle as f32 / 100.0);
+            input.throttle = hover.saturating_add(to_i16(throttle));
+            input.yaw = to_i16(self.heading.update(wrap_angle(home.course - state.heading), dt));
+            let tilt = if climbed && !loitering { -self.multirotor_tilt } else { 0.0 };
+            input.pitch = self.to_attitude_input(tilt, state.pitch);
+            input.roll = self.to_attitude_input(0.0, state.roll);
+            return input;
+        }
+
+        let target = match landing {
+            true => state.altitude - landing_speed * config.tecs.time_constant as f32,
+            false => state.altitude + target - home.height,
+        };
+        let speed = config.tecs.cruise_speed as f32;
+        let (throttle, pitch) =
+            self.tecs.update(state.altitude, state.climb_rate, state.speed, target, speed);
+        let bank = if climbed { home.bank } else { 0.0 };
+        input.throttle = to_throttle(throttle);
+        input.pitch = self.to_attitude_input(pitch, state.pitch);
+        input.roll = self.to_attitude_input(bank, state.roll);
+        input.yaw = 0;
+        input
+    }
+
+    pub fn loiter(
+        &mut self,
+        mut input: ControlInput,
+        state: &State,
+        bank: f32,
+        dt: f32,
+    ) -> ControlInput {
+        if self.multirotor {
+            let hover = to_throttle(config::get().autopilot.hover_throttle as f32 / 100.0);
+            input.throttle = hover.saturating_add(to_i16(self.hold_altitude(state, dt)));
+            input.pitch = self.to_attitude_input(0.0, state.pitch);
+            input.roll = self.to_attitude_input(0.0, state.roll);
+            return input;
+        }
+
+        let target = *self.target_altitude.get_or_insert(state.altitude);
+        let speed = config::get().autopilot.tecs.cruise_speed as f32;
+        let (throttle, pitch) =
+            self.tecs.update(state.altitude, state.climb_rate, state.speed, target, speed);
+        input.throttle = to_throttle(throttle);
+        input.pitch = self.to_attitude_input(pitch, state.pitch);
+        input.roll = self.to_attitude_input(bank, state.roll);
+        input.yaw = 0;
+        input
+    }
+
+    fn home(&mut self) -> Option<Home> {
+        let (position, steerpoint) = self.navigation.as_mut()?.read();
+        let guidance = self.guidance.as_mut()?.read();
+        let delta = (steerpoint.waypoint.position - position).convert(|v| v as f32);
+        let (x, y) = (delta.x.value(), delta.y.value());
+        let height = position.altitude - steerpoint.waypoint.position.altitude;
+        Some(Home {
+            height: height.convert(|v| v as f32).to_unit(Meter).value(),
+            distance: (x * x + y * y).sqrt(),
+            course: guidance.course as f32 / DEGREE_PER_DAG,
+            bank: guidance.bank,
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        input: ControlInput,
+        auxiliary: &AuxiliaryInput,
+        failsafe: FailsafeStage,
+        now: Duration,
+    ) -> ControlInput {
+        let dt = self.time.map(|time| (now - time).as_secs_f32()).unwrap_or(0.0);
+        self.time = Some(now);
+        let mode = self.flight_mode.as_mut().map(|m| m.read()).unwrap_or_default();
+        let hold = match failsafe {
+            FailsafeStage::Normal | FailsafeStage::Hold => self.hold(mode, auxiliary),
+            _ => Hold::default(),
+        };
+        let altitude = self.altimeter.read().convert(|v| v as f32).to_unit(Meter);
+        let euler = Euler::from(self.attitude.read());
+        let velocity: Vector3<f32> = self.speedometer.read().into();
+        let state = State {
+            altitude: altitude.value(),
+            climb_rate: velocity[2],
+            heading: -euler.yaw,
+            roll: euler.roll,
+            pitch: euler.pitch,
+            speed: velocity.norm(),
+        };
+        let navigable = self.validity.as_mut().map(|v| v.read().horizontal).unwrap_or(false);
+        let rth = mode == FlightMode::ReturnToHome || failsafe == FailsafeStage::ReturnToHome;
+        let rth = rth && navigable;
+        if let Some(home) = if rth { self.home() } else { None } {
+            if self.rth.is_none() {
+                self.release_altitude();
+                self.release_heading();
+            }
+            return self.return_to_home(input, &state, &home, dt);
+        }
+        if self.rth.take().is_some() {
+            self.tecs.reset();
+            self.release_altitude();
+            self.release_heading();
+        }
+        let guidance = self.guidance.as_mut().map(|g| g.read()).filter(|_| navigable);
+        if let Some(guidance) = guidance.filter(|_| mode == FlightMode::Loiter) {
+            if !self.loitering {
+                self.loitering = true;
+                self.release_altitude();
+                self.release_heading();
+            }
+            return self.loiter(input, &state, guidance.bank, dt);
+        }
+        if self.loitering {
+            self.loitering = false;
+            self.tecs.reset();
+            self.release_altitude();
+        }
+        self.control(hold, input, &state, dt)
+    }
+}
+
+mod test {
+    #[cfg(test)]
+    fn autopilot() -> super::Autopilot {
+        use alloc::boxed::Box;
+
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::NoDataSource;
+
+        config::replace(Config::default());
+        let (altimeter, speedometer, attitude) =
+            (NoDataSource::new(), NoDataSource::new(), NoDataSource::new());
+        super::Autopilot::new(Box::new(altimeter), Box::new(speedometer), Box::new(attitude), 50)
+    }
+
+    #[test]
+    #[serial]
+    fn test_altitude_hold() {
+        use crate::datastructures::input::ControlInput;
+
+        use super::{Hold, State};
+
+        let mut autopilot = autopilot();
+        let hold = Hold { altitude: true, heading: false };
+        let mut state = State { altitude: 100.0, climb_rate: 2.0, ..Default::default() };
+        let dt = 1.0 / 50.0;
+        for _ in 0..30 * 50 {
+            let output = autopilot.control(hold, ControlInput::default(), &state, dt);
+            assert_eq!(output.roll, 0);
+            let input = output.pitch as f32 / i16::MAX as f32;
+            state.climb_rate += (10.0 * input - state.climb_rate) * dt;
+            state.altitude += state.climb_rate * dt;
+        }
+        assert!((state.altitude - 100.0).abs() < 0.5);
+        assert!(state.climb_rate.abs() < 0.1);
+
+        // Stick takes over then recaptures target
+        let input = ControlInput { pitch: i16::MAX / 2, ..Default::default() };
+        let output = autopilot.control(hold, input, &state, dt);
+        assert_eq!(output.pitch, i16::MAX / 2);
+        state.altitude = 150.0;
+        let output = autopilot.control(hold, ControlInput::default(), &state, dt);
+        assert!(output.pitch.abs() < 1000);
+    }
+
+    #[test]
+    #[serial]
+    fn test_heading_hold() {
+        use crate::datastructures::input::ControlInput;
+        use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
+
+        use super::{Hold, State};
+
+        let mut autopilot = autopilot();
+        let hold = Hold { altitude: false, heading: true };
+        let mut state = State::default();
+        let dt = 1.0 / 50.0;
+        autopilot.control(hold, ControlInput::default(), &state, dt);
+
+        // Gust turns aircraft 30 degree to the left
+        state.heading = -30.0 / DEGREE_PER_DAG;
+        let mut turn_rate = 0.0;
+        for _ in 0..20 * 50 {
+            let output = autopilot.control(hold, ControlInput::default(), &state, dt);
+            assert_eq!(output.pitch, 0);
+            let input = output.roll as f32 / i16::MAX as f32;
+            turn_rate += (0.5 * input - turn_rate) * 2.0 * dt;
+            state.heading += turn_rate * dt;
+        }
+        assert!((state.heading * DEGREE_PER_DAG).abs() < 1.0);
+
+        // Shortest turn across south
+        autopilot.control(Hold::default(), ControlInput::default(), &state, dt);
+        state.heading = 170.0 / DEGREE_PER_DAG;
+        autopilot.control(hold, ControlInput::default(), &state, dt);
+        state.heading = -170.0 / DEGREE_PER_DAG;
+        let output = autopilot.control(hold, ControlInput::default(), &state, dt);
+        assert!(output.roll < 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_return_to_home() {
+        use crate::config::autopilot::RthAction;
+        use crate::config::{self, Config};
+        use crate::datastructures::input::ControlInput;
+
+        use super::{Home, State};
+
+        let mut rth = autopilot();
+        let state = State { altitude: 110.0, speed: 15.0, ..Default::default() };
+        let dt = 1.0 / 50.0;
+
+        // Climbs wings level below minimum altitude
+        let mut home = Home { height: 10.0, distance: 500.0, course: 0.0, bank: 0.5 };
+        let output = rth.return_to_home(ControlInput::default(), &state, &home, dt);
+        assert_eq!(output.roll, 0);
+        assert!(output.pitch > 0 && output.throttle > 0);
+
+        // Turns home once minimum altitude reached
+        home.height = 50.0;
+        let output = rth.return_to_home(ControlInput::default(), &state, &home, dt);
+        assert!(output.roll > 0);
+        home.height = 40.0;
+        let output = rth.return_to_home(ControlInput::default(), &state, &home, dt);
+        assert!(output.roll > 0);
+
+        // Descends when landing at home
+        let mut landing = autopilot();
+        let mut config = Config::default();
+        config.autopilot.rth.action = RthAction::Land;
+        config::replace(config);
+        let home = Home { height: 50.0, distance: 20.0, course: 0.0, bank: 0.5 };
+        let output = landing.return_to_home(ControlInput::default(), &state, &home, dt);
+        assert!(output.pitch < 0 && output.throttle < 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_loiter() {
+        use crate::datastructures::input::ControlInput;
+
+        use super::State;
+
+        let mut autopilot = autopilot();
+        let mut state = State { altitude: 100.0, speed: 15.0, ..Default::default() };
+        let dt = 1.0 / 50.0;
+
+        // Banks by guidance and holds altitude where engaged
+        let output = autopilot.loiter(ControlInput::default(), &state, 0.5, dt);
+        assert!(output.roll > 0);
+        state.altitude = 90.0;
+        let output = autopilot.loiter(ControlInput::default(), &state, 0.5, dt);
+        assert!(output.pitch > 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_multirotor_hover() {
+        use alloc::boxed::Box;
+
+        use crate::config::aircraft::Configuration;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::NoDataSource;
+        use crate::datastructures::input::ControlInput;
+
+        use super::{Autopilot, State};
+
+        let mut config = Config::default();
+        config.aircraft.configuration = Configuration::QuadX;
+        config::replace(config);
+        let (altimeter, speedometer, attitude) =
+            (NoDataSource::new(), NoDataSource::new(), NoDataSource::new());
+        let mut autopilot =
+            Autopilot::new(Box::new(altimeter), Box::new(speedometer), Box::new(attitude), 50);
+
+        // Throttle stick at bottom after failsafe, hovers at engaged altitude
+        let input = ControlInput { throttle: i16::MIN, ..Default::default() };
+        let state = State { altitude: 100.0, ..Default::default() };
+        let output = autopilot.loiter(input, &state, 0.5, 1.0 / 50.0);
+        assert!(output.throttle.abs() < 100);
+        assert_eq!(output.roll, 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_multirotor_altitude_hold() {
+        use alloc::boxed::Box;
+
+        use crate::config::aircraft::Configuration;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::NoDataSource;
+        use crate::datastructures::input::ControlInput;
+
+        use super::{Autopilot, Hold, State};
+
+        let mut config = Config::default();
+        config.aircraft.configuration = Configuration::QuadX;
+        config.autopilot.hover_throttle = 40;
+        config::replace(config);
+        let (altimeter, speedometer, attitude) =
+            (NoDataSource::new(), NoDataSource::new(), NoDataSource::new());
+        let mut autopilot =
+            Autopilot::new(Box::new(altimeter), Box::new(speedometer), Box::new(attitude), 50);
+
+        // Throttle stick centred, holds altitude around hover throttle like loiter does
+        let hold = Hold { altitude: true, heading: false };
+        let input = ControlInput { throttle: 0, ..Default::default() };
+        let mut state = State { altitude: 100.0, climb_rate: 2.0, ..Default::default() };
+        let dt = 1.0 / 50.0;
+        for _ in 0..30 * 50 {
+            let output = autopilot.control(hold, input, &state, dt);
+            let throttle = (output.throttle as f32 - i16::MIN as f32) / u16::MAX as f32;
+            state.climb_rate += (10.0 * (throttle - 0.4) - state.climb_rate) * dt;
+            state.altitude += state.climb_rate * dt;
+        }
+        assert!((state.altitude - 100.0).abs() < 0.5);
+        assert!(state.climb_rate.abs() < 0.1);
+        let output = autopilot.control(hold, input, &state, dt);
+        let hover = (0.4 * u16::MAX as f32) as i32 + i16::MIN as i32;
+        assert!((output.throttle as i32 - hover).abs() < 1000);
+    }
+
+    #[test]
+    #[serial]
+    fn test_return_to_home_after_rc_loss() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use crate::components::failsafe::fly_through_rc_loss;
+        use crate::config::receiver::FailsafeAction;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::{DataWriter, NoDataSource};
+        use crate::datastructures::failsafe::FailsafeStage;
+        use crate::datastructures::ins::Validity;
+
+        use super::Autopilot;
+
+        let mut config = Config::default();
+        config.receiver.failsafe.action = FailsafeAction::ReturnToHome;
+        config::replace(config);
+
+        let navigation = Rc::new(SingularData::default());
+        let guidance = Rc::new(SingularData::default());
+        let validity = Rc::new(SingularData::default());
+        validity.write(Validity { horizontal: true, vertical: true, velocity: true });
+        let (altimeter, speedometer, attitude) =
+            (NoDataSource::new(), NoDataSource::new(), NoDataSource::new());
+        let mut autopilot =
+            Autopilot::new(Box::new(altimeter), Box::new(speedometer), Box::new(attitude), 50);
+        autopilot.set_navigation(
+            Box::new(SingularDataSource::new(&navigation)),
+            Box::new(SingularDataSource::new(&guidance)),
+            Box::new(SingularDataSource::new(&validity)),
+        );
+
+        // RC lost after 1 second at home, climbs to minimum altitude,
+        // levels with failsafe throttle once horizontal position invalid at 6 seconds
+        let stages = fly_through_rc_loss(Some(autopilot), 7, None, |i, stage, output| {
+            if i + 1 == 6 * 50 {
+                validity.write(Validity { horizontal: false, vertical: true, velocity: true });
+            }
+            if stage == FailsafeStage::ReturnToHome && i < 6 * 50 {
+                assert!(output.pitch > 0 && output.throttle > -13108);
+                assert_eq!(output.roll, 0);
+            } else if i >= 6 * 50 {
+                assert_eq!((output.throttle, output.pitch, output.roll), (-13108, 0, 0));
+            }
+        });
+        let expected = [
+            FailsafeStage::Normal,
+            FailsafeStage::Hold,
+            FailsafeStage::Level,
+            FailsafeStage::ReturnToHome,
+        ];
+        assert_eq!(&stages[..], &expected[..]);
+    }
+}
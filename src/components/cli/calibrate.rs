@@ -0,0 +1,465 @@
+use alloc::rc::Rc;
+use core::time::Duration;
+
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::Vector3;
+
+use crate::config;
+use crate::datastructures::calibration::EscCalibration;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::DataWriter;
+use crate::datastructures::measurement::{Acceleration, Axes, Gain, Gyro};
+use crate::sys::timer::get_jiffies;
+
+const ACCEL_SAMPLES: usize = 50;
+const ACCEL_SAMPLE_INTERVAL: Duration = Duration::from_millis(20);
+const MAX_GYRO_VARIANCE: f32 = 1.0; // (degree per second)²
+const ESC_CALIBRATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Motor outputs follow calibration stage only while disarmed,
+/// stage falls back to idle when next step not given in time
+pub struct EscCalibrator {
+    stage: EscCalibration,
+    since: Duration,
+    output: Rc<SingularData<EscCalibration>>,
+}
+
+impl EscCalibrator {
+    pub fn new() -> Self {
+        let output = Rc::new(SingularData::default());
+        Self { stage: EscCalibration::Idle, since: Duration::default(), output }
+    }
+
+    pub fn reader(&self) -> SingularDataSource<EscCalibration> {
+        SingularDataSource::new(&self.output)
+    }
+
+    fn set(&mut self, stage: EscCalibration) {
+        self.stage = stage;
+        self.since = get_jiffies();
+        self.output.write(stage);
+    }
+
+    /// Should be polled even if CLI is disconnected
+    pub fn expire(&mut self, now: Duration) {
+        if self.stage != EscCalibration::Idle && now - self.since > ESC_CALIBRATION_TIMEOUT {
+            warn!("ESC calibration timeout");
+            self.set(EscCalibration::Idle);
+        }
+    }
+}
+
+// Max throttle sent only after props-off confirmed, then min throttle
+fn calibrate_esc(
+    writer: &mut impl core::fmt::Write,
+    step: Option<&str>,
+    armed: bool,
+    esc: &mut EscCalibrator,
+) -> core::fmt::Result {
+    if armed {
+        esc.set(EscCalibration::Idle);
+        return writeln!(writer, "Disarm before calibrating ESC");
+    }
+    match (step, esc.stage) {
+        (None, _) => {
+            writeln!(writer, "Remove all propellers, then `calibrate esc props-off` to continue")
+        }
+        (Some("props-off"), EscCalibration::Idle) => {
+            esc.set(EscCalibration::Max);
+            writeln!(writer, "Sending max throttle, power up ESC and wait for tones,")?;
+            writeln!(writer, "then `calibrate esc min` within 30 seconds")
+        }
+        (Some("min"), EscCalibration::Max) => {
+            esc.set(EscCalibration::Min);
+            writeln!(writer, "Sending min throttle, wait for tones, then `calibrate esc done`")
+        }
+        (Some("done"), _) => {
+            esc.set(EscCalibration::Idle);
+            writeln!(writer, "ESC calibration done")
+        }
+        _ => writeln!(writer, "Unexpected calibration step"),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Orientation {
+    Flat,
+    Reverse,
+    RollLeft,
+    RollRight,
+    PitchDown,
+    PitchUp,
+}
+
+const ORIENTATIONS: [Orientation; 6] = [
+    Orientation::Flat,
+    Orientation::Reverse,
+    Orientation::RollLeft,
+    Orientation::RollRight,
+    Orientation::PitchDown,
+    Orientation::PitchUp,
+];
+
+impl Orientation {
+    // Axis along gravity with its reading sign
+    fn axis(self) -> (usize, bool) {
+        match self {
+            Self::Flat => (2, false),
+            Self::Reverse => (2, true),
+            Self::RollLeft => (0, false),
+            Self::RollRight => (0, true),
+            Self::PitchDown => (1, false),
+            Self::PitchUp => (1, true),
+        }
+    }
+}
+
+impl Into<&str> for Orientation {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Flat => "flat",
+            Self::Reverse => "reverse",
+            Self::RollLeft => "roll-left",
+            Self::RollRight => "roll-right",
+            Self::PitchDown => "pitch-down",
+            Self::PitchUp => "pitch-up",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum SampleError {
+    Moving,
+    Tilted,
+}
+
+/// Per-axis bias and gain from raw readings with axis pointing down and up,
+/// calibrated reading is `(raw - bias) * gain / sensitive`
+pub fn solve(readings: &[(f32, f32); 3], sensitive: i32) -> Option<(Axes, Gain)> {
+    let mut bias = [0i32; 3];
+    let mut gain = [0u16; 3];
+    for (i, &(down, up)) in readings.iter().enumerate() {
+        let center = (down + up) / 2.0;
+        let half_span = up - center;
+        if half_span <= 0.0 {
+            return None;
+        }
+        let value = sensitive as f32 * sensitive as f32 / half_span;
+        if value > u16::MAX as f32 {
+            return None;
+        }
+        bias[i] = center as i32;
+        gain[i] = value as u16;
+    }
+    let axes = Axes { x: bias[0], y: bias[1], z: bias[2] };
+    Some((axes, Gain { x: gain[0], y: gain[1], z: gain[2] }))
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Collector {
+    sum: Vector3<f32>,
+    gyro_sum: Vector3<f32>,
+    gyro_square_sum: Vector3<f32>,
+    count: usize,
+    since: Duration,
+}
+
+/// Six-position accelerometer calibration, each orientation averaged only while still,
+/// samples collected one per interval without blocking
+pub struct AccelCalibrator {
+    readings: [Option<f32>; 6],
+    sensitive: i32,
+    collector: Option<Collector>,
+}
+
+impl AccelCalibrator {
+    pub fn new() -> Self {
+        Self { readings: [None; 6], sensitive: 0, collector: None }
+    }
+
+    fn reset(&mut self) {
+        self.readings = [None; 6];
+        self.collector = None;
+    }
+
+    fn remaining(&self) -> usize {
+        self.readings.iter().filter(|r| r.is_none()).count()
+    }
+
+    pub fn sampling(&self) -> bool {
+        self.collector.is_some()
+    }
+
+    fn start(&mut self) {
+        self.collector = Some(Collector::default());
+    }
+
+    /// Takes one raw sample once interval elapsed, orientation given after enough samples
+    fn collect(
+        &mut self,
+        now: Duration,
+        acceleration: &Acceleration,
+        gyro: &Gyro,
+    ) -> Option<Result<Orientation, SampleError>> {
+        let collector = self.collector.as_mut()?;
+        if collector.count > 0 && now - collector.since < ACCEL_SAMPLE_INTERVAL {
+            return None;
+        }
+        let axes = acceleration.0.axes;
+        collector.sum += Vector3::new(axes.x as f32, axes.y as f32, axes.z as f32);
+        let gyro: Vector3<f32> = (*gyro).into();
+        collector.gyro_sum += gyro;
+        collector.gyro_square_sum += gyro.component_mul(&gyro);
+        collector.count += 1;
+        collector.since = now;
+        self.sensitive = acceleration.0.sensitive;
+        if collector.count < ACCEL_SAMPLES {
+            return None;
+        }
+        let collector = self.collector.take()?;
+        Some(self.sample(&collector))
+    }
+
+    fn sample(&mut self, collector: &Collector) -> Result<Orientation, SampleError> {
+        let count = collector.count as f32;
+        let average = collector.sum / count;
+        let gyro_average = collector.gyro_sum / count;
+        let variance =
+            collector.gyro_square_sum / count - gyro_average.component_mul(&gyro_average);
+        if variance.max() > MAX_GYRO_VARIANCE {
+            return Err(SampleError::Moving);
+        }
+
+        let normalized = average.try_normalize(0.0).ok_or(SampleError::Tilted)?;
+        for (index, &orientation) in ORIENTATIONS.iter().enumerate() {
+            let (axis, positive) = orientation.axis();
+            let value = if positive { normalized[axis] } else { -normalized[axis] };
+            if value > 0.8 {
+                self.readings[index] = Some(average[axis]);
+                return Ok(orientation);
+            }
+        }
+        Err(SampleError::Tilted)
+    }
+
+    fn solve(&self) -> Option<(Axes, Gain)> {
+        let mut readings = [(0.0, 0.0); 3];
+        for (index, &orientation) in ORIENTATIONS.iter().enumerate() {
+            let (axis, positive) = orientation.axis();
+            let reading = self.readings[index]?;
+            match positive {
+                true => readings[axis].1 = reading,
+                false => readings[axis].0 = reading,
+            }
+        }
+        solve(&readings, self.sensitive)
+    }
+}
+
+fn calibrate_accel(
+    writer: &mut impl core::fmt::Write,
+    step: Option<&str>,
+    armed: bool,
+    accel: &mut AccelCalibrator,
+) -> core::fmt::Result {
+    if armed {
+        accel.reset();
+        return writeln!(writer, "Disarm before calibrating accelerometer");
+    }
+    match step {
+        None => {
+            accel.reset();
+            writeln!(writer, "Hold still in each of six orientations: flat, reverse,")?;
+            writeln!(writer, "roll-left, roll-right, pitch-down and pitch-up,")?;
+            writeln!(writer, "then `calibrate accel sample` for each")
+        }
+        Some("sample") => {
+            accel.start();
+            writeln!(writer, "Sampling, hold still")
+        }
+        _ => writeln!(writer, "Unexpected calibration step"),
+    }
+}
+
+/// Expected to be polled with raw readings, i.e. without accelerometer bias and gain applied,
+/// reports and solves once sampling of an orientation finished
+pub fn collect_accel(
+    writer: &mut impl core::fmt::Write,
+    accel: &mut AccelCalibrator,
+    now: Duration,
+    acceleration: &Acceleration,
+    gyro: &Gyro,
+) -> core::fmt::Result {
+    let orientation: &str = match accel.collect(now, acceleration, gyro) {
+        None => return Ok(()),
+        Some(Ok(orientation)) => orientation.into(),
+        Some(Err(SampleError::Moving)) => return writeln!(writer, "Moving, hold still"),
+        Some(Err(SampleError::Tilted)) => return writeln!(writer, "Not aligned to any axis"),
+    };
+    let remaining = accel.remaining();
+    writeln!(writer, "Sampled {}, {} orientations remaining", orientation, remaining)?;
+    if remaining > 0 {
+        return Ok(());
+    }
+    let (bias, gain) = match accel.solve() {
+        Some(result) => result,
+        None => return writeln!(writer, "Calibration failed, start over"),
+    };
+    accel.reset();
+    let mut config = config::get().clone();
+    config.imu.accelerometer.bias = bias;
+    config.imu.accelerometer.gain = gain;
+    config::replace(config);
+    writeln!(writer, "bias: {} {} {}", bias.x, bias.y, bias.z)?;
+    writeln!(writer, "gain: {} {} {}", gain.x, gain.y, gain.z)?;
+    writeln!(writer, "`save` to keep calibration")
+}
+
+pub fn calibrate(
+    writer: &mut impl core::fmt::Write,
+    line: &str,
+    armed: bool,
+    esc: Option<&mut EscCalibrator>,
+    accel: Option<&mut AccelCalibrator>,
+) -> core::fmt::Result {
+    let mut split = line.split(' ');
+    split.next();
+    match (split.next(), esc, accel) {
+        (Some("esc"), Some(esc), _) => calibrate_esc(writer, split.next(), armed, esc),
+        (Some("esc"), None, _) => writeln!(writer, "ESC calibration not available"),
+        (Some("accel"), _, Some(accel)) => calibrate_accel(writer, split.next(), armed, accel),
+        (Some("accel"), _, None) => writeln!(writer, "Accelerometer calibration not available"),
+        _ => writeln!(writer, "Usage: calibrate esc|accel"),
+    }
+}
+
+mod test {
+    #[test]
+    fn test_calibrate_esc() {
+        extern crate std;
+
+        use core::time::Duration;
+        use std::string::String;
+
+        use crate::datastructures::calibration::EscCalibration;
+        use crate::datastructures::data_source::StaticData;
+
+        use super::{calibrate, EscCalibrator};
+
+        let mut esc = EscCalibrator::new();
+        let mut reader = esc.reader();
+        let mut output = String::new();
+        let mut step = |line, armed| {
+            calibrate(&mut output, line, armed, Some(&mut esc), None).ok();
+        };
+        step("calibrate esc min", false);
+        assert_eq!(reader.read(), EscCalibration::Idle);
+        step("calibrate esc props-off", true);
+        assert_eq!(reader.read(), EscCalibration::Idle);
+        step("calibrate esc props-off", false);
+        assert_eq!(reader.read(), EscCalibration::Max);
+        step("calibrate esc min", false);
+        assert_eq!(reader.read(), EscCalibration::Min);
+        step("calibrate esc done", false);
+        assert_eq!(reader.read(), EscCalibration::Idle);
+
+        step("calibrate esc props-off", false);
+        assert_eq!(reader.read(), EscCalibration::Max);
+        esc.expire(Duration::from_secs(30));
+        assert_eq!(reader.read(), EscCalibration::Max);
+        esc.expire(Duration::from_secs(31));
+        assert_eq!(reader.read(), EscCalibration::Idle);
+    }
+
+    #[cfg(test)]
+    fn sample(
+        accel: &mut super::AccelCalibrator,
+        raw: (i32, i32, i32),
+        wobble: i32,
+    ) -> alloc::string::String {
+        use alloc::string::String;
+        use core::time::Duration;
+
+        use crate::datastructures::measurement::{Acceleration, Axes, Gyro, Measurement};
+
+        use super::{calibrate, collect_accel};
+
+        let mut output = String::new();
+        calibrate(&mut output, "calibrate accel sample", false, None, Some(accel)).ok();
+        // Readings between sample intervals are skipped
+        for i in 0..super::ACCEL_SAMPLES as i32 * 4 {
+            let noise = (i / 4) % 3 - 1;
+            let axes = Axes { x: raw.0 + noise, y: raw.1 - noise, z: raw.2 + noise };
+            let acceleration = Acceleration(Measurement { axes, sensitive: 4096 });
+            let rate = if (i / 4) % 2 == 0 { wobble } else { -wobble };
+            let gyro = Gyro { axes: Axes { x: rate + noise, y: noise, z: 0 }, sensitive: 16 };
+            let now = Duration::from_millis(5 * i as u64);
+            collect_accel(&mut output, accel, now, &acceleration, &gyro).ok();
+        }
+        output
+    }
+
+    #[test]
+    fn test_solve_accel() {
+        use super::solve;
+
+        // Scale 4000, 4150 and 4096 per g with bias -236, 22 and -713
+        let readings = [(-4236.0, 3764.0), (-4128.0, 4172.0), (-4809.0, 3383.0)];
+        let (bias, gain) = solve(&readings, 4096).unwrap();
+        assert_eq!((bias.x, bias.y, bias.z), (-236, 22, -713));
+        assert_eq!((gain.x, gain.y, gain.z), (4194, 4042, 4096));
+        assert!(solve(&[(1.0, -1.0), (-1.0, 1.0), (-1.0, 1.0)], 4096).is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_calibrate_accel() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::{self, Config};
+        use crate::datastructures::measurement::{Axes, Measurement};
+
+        use super::{calibrate, AccelCalibrator};
+
+        config::replace(Config::default());
+        let mut accel = AccelCalibrator::new();
+        let mut output = String::new();
+        calibrate(&mut output, "calibrate accel", false, None, Some(&mut accel)).ok();
+
+        let (bias, scale) = ((-236, 22, -713), (4000, 4150, 4096));
+        let (x, y, z) = bias;
+        let orientations = [
+            (x, y, z - scale.2),
+            (x, y, z + scale.2),
+            (x - scale.0, y, z),
+            (x + scale.0, y, z),
+            (x, y - scale.1, z),
+            (x, y + scale.1, z),
+        ];
+        // Rejected while rotating
+        assert_eq!(
+            sample(&mut accel, orientations[0], 80),
+            "Sampling, hold still\nMoving, hold still\n"
+        );
+        assert_eq!(accel.remaining(), 6);
+        assert!(!accel.sampling());
+        // Starts over once all six orientations solved
+        for (i, &raw) in orientations.iter().enumerate() {
+            sample(&mut accel, raw, 8);
+            assert_eq!(accel.remaining(), if i < 5 { 5 - i } else { 6 });
+        }
+
+        let accelerometer = &config::get().imu.accelerometer;
+        let (bias, gain) = (accelerometer.bias, accelerometer.gain.into());
+        for &raw in orientations.iter() {
+            let (x, y, z) = raw;
+            let axes = Axes { x, y, z };
+            let calibrated = Measurement { axes, sensitive: 4096 }.zero(&bias).gain(&gain);
+            let g = calibrated.axes.x.abs() + calibrated.axes.y.abs() + calibrated.axes.z.abs();
+            assert!((g - 4096).abs() <= 4);
+        }
+    }
+}
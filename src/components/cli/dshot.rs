@@ -0,0 +1,64 @@
+use alloc::rc::Rc;
+
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::DataWriter;
+use crate::drivers::dshot::Command;
+
+/// Special commands for DShot ESCs, motor outputs send them only while disarmed
+pub struct DShotCommander {
+    output: Rc<SingularData<Command>>,
+}
+
+impl DShotCommander {
+    pub fn new() -> Self {
+        Self { output: Rc::new(SingularData::default()) }
+    }
+
+    pub fn reader(&self) -> SingularDataSource<Command> {
+        SingularDataSource::new(&self.output)
+    }
+}
+
+pub fn dshot(
+    writer: &mut impl core::fmt::Write,
+    line: &str,
+    armed: bool,
+    commander: &mut DShotCommander,
+) -> core::fmt::Result {
+    if armed {
+        return writeln!(writer, "Disarm before sending DShot command");
+    }
+    let mut split = line.split(' ');
+    split.next();
+    match split.next().map(|command| command.parse::<Command>()) {
+        Some(Ok(command)) => {
+            commander.output.write(command);
+            writeln!(writer, "Sending {:?} {} times", command, command.repeat())
+        }
+        _ => writeln!(writer, "Usage: dshot stop|beep[1-5]|info|3d-off|3d-on|save|normal|reversed"),
+    }
+}
+
+mod test {
+    #[test]
+    fn test_dshot_command() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::datastructures::data_source::OptionData;
+        use crate::drivers::dshot::Command;
+
+        use super::{dshot, DShotCommander};
+
+        let mut commander = DShotCommander::new();
+        let mut reader = commander.reader();
+        let mut output = String::new();
+        dshot(&mut output, "dshot beep2", true, &mut commander).ok();
+        assert_eq!(reader.read(), None);
+        dshot(&mut output, "dshot unknown", false, &mut commander).ok();
+        assert_eq!(reader.read(), None);
+        dshot(&mut output, "dshot save", false, &mut commander).ok();
+        assert_eq!(reader.read(), Some(Command::SaveSettings));
+    }
+}
@@ -1,6 +1,9 @@
+pub mod calibrate;
 mod config;
+pub mod dshot;
 pub mod memory;
 
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::fmt;
 
@@ -10,8 +13,12 @@ use crate::alloc;
 use crate::components::logger;
 use crate::components::telemetry::TelemetryData;
 use crate::datastructures::data_source::StaticData;
+use crate::datastructures::measurement::{Acceleration, Gyro};
 use crate::drivers::serial::Readline;
-use crate::sys::timer::SysTimer;
+use crate::sys::timer::{get_jiffies, SysTimer};
+
+use calibrate::{AccelCalibrator, EscCalibrator};
+use dshot::DShotCommander;
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 const REVISION: &'static str = git_version!();
@@ -24,6 +31,11 @@ pub struct CLI<T> {
     reboot: fn() -> !,
     bootloader: fn() -> !,
     free: fn() -> (usize, usize),
+    esc_calibrator: Option<EscCalibrator>,
+    accel_calibrator: Option<AccelCalibrator>,
+    accelerometer: Option<Box<dyn StaticData<Acceleration>>>,
+    gyroscope: Option<Box<dyn StaticData<Gyro>>>,
+    dshot_commander: Option<DShotCommander>,
 }
 
 impl<T: StaticData<TelemetryData>> CLI<T> {
@@ -40,9 +52,58 @@ impl<T: StaticData<TelemetryData>> CLI<T> {
             reboot,
             bootloader,
             free,
+            esc_calibrator: None,
+            accel_calibrator: None,
+            accelerometer: None,
+            gyroscope: None,
+            dshot_commander: None,
+        }
+    }
+
+    pub fn set_esc_calibrator(&mut self, esc_calibrator: EscCalibrator) {
+        self.esc_calibrator = Some(esc_calibrator);
+    }
+
+    /// Raw readings from driver, so existing accelerometer calibration is bypassed
+    pub fn set_accel_calibrator(
+        &mut self,
+        accelerometer: Box<dyn StaticData<Acceleration>>,
+        gyroscope: Box<dyn StaticData<Gyro>>,
+    ) {
+        self.accel_calibrator = Some(AccelCalibrator::new());
+        self.accelerometer = Some(accelerometer);
+        self.gyroscope = Some(gyroscope);
+    }
+
+    pub fn set_dshot_commander(&mut self, dshot_commander: DShotCommander) {
+        self.dshot_commander = Some(dshot_commander);
+    }
+
+    /// Resets unfinished ESC calibration, even if no one is interacting
+    pub fn expire(&mut self) {
+        if let Some(esc_calibrator) = self.esc_calibrator.as_mut() {
+            esc_calibrator.expire(get_jiffies());
         }
     }
 
+    /// Collects accelerometer calibration samples without blocking, expected to be polled
+    pub fn collect(&mut self, serial: &mut impl fmt::Write) -> fmt::Result {
+        let accel = match self.accel_calibrator.as_mut().filter(|accel| accel.sampling()) {
+            Some(accel) => accel,
+            None => return Ok(()),
+        };
+        let (accelerometer, gyroscope) = match (&mut self.accelerometer, &mut self.gyroscope) {
+            (Some(accelerometer), Some(gyroscope)) => (accelerometer, gyroscope),
+            _ => return Ok(()),
+        };
+        let (acceleration, gyro) = (accelerometer.read(), gyroscope.read());
+        calibrate::collect_accel(serial, accel, get_jiffies(), &acceleration, &gyro)?;
+        if !accel.sampling() {
+            write!(serial, "{}", PROMPT)?;
+        }
+        Ok(())
+    }
+
     pub fn interact(&mut self, serial: &mut (impl Readline + fmt::Write)) -> fmt::Result {
         let line = match serial.readline(&mut self.vec) {
             Some(line) => unsafe { core::str::from_utf8_unchecked(line) },
@@ -51,7 +112,21 @@ impl<T: StaticData<TelemetryData>> CLI<T> {
         if !line.starts_with('#') {
             if let Some(first_word) = line.split(' ').next() {
                 match first_word {
+                    "arming" => writeln!(serial, "{}", self.telemetry.read().status.arming)?,
                     "bootloader" => (self.bootloader)(),
+                    "calibrate" => {
+                        let armed = self.telemetry.read().status.arming.armed;
+                        let esc = self.esc_calibrator.as_mut();
+                        let accel = self.accel_calibrator.as_mut();
+                        calibrate::calibrate(serial, line, armed, esc, accel)?
+                    }
+                    "dshot" => {
+                        let armed = self.telemetry.read().status.arming.armed;
+                        match self.dshot_commander.as_mut() {
+                            Some(commander) => dshot::dshot(serial, line, armed, commander)?,
+                            None => writeln!(serial, "DShot not available")?,
+                        }
+                    }
                     "dump" => memory::dump(line, serial)?,
                     "free" => {
                         let (used, free) = (self.free)();
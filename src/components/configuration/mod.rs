@@ -1,4 +1,129 @@
 pub mod fixed_wing;
+pub mod multirotor;
 pub mod pwm;
 
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use embedded_hal::PwmPin;
+
+use crate::components::mixer::evaluate;
+use crate::config;
+use crate::config::mixer::Rules;
+use crate::config::peripherals::pwm::{Motor, PWM as PwmConfig};
+use crate::datastructures::arming::Arming;
+use crate::datastructures::calibration::EscCalibration;
+use crate::datastructures::data_source::{OptionData, StaticData};
+use crate::datastructures::input::{AuxiliaryInput, ControlInput};
+use crate::drivers::dshot::Command;
+use crate::sys::timer::get_jiffies;
+
+use pwm::{slew, to_motor_output, to_servo_pwm_duty, CommandRepeater};
+
 pub use fixed_wing::FixedWing;
+pub use multirotor::Multirotor;
+
+type PWM = Box<dyn PwmPin<Duty = u16>>;
+/// Name, pin and actual rate of output, timer shared by outputs runs at single rate
+pub type Output = (&'static str, PWM, u16);
+
+/// Config and mixer rules of each named output, none if output not configured
+fn load_configs(pwms: &[Output]) -> Vec<Option<(PwmConfig, Rules)>> {
+    let config = config::get();
+    let configuration = config.aircraft.configuration;
+    let mut configs = Vec::with_capacity(pwms.len());
+    for (name, _, _) in pwms.iter() {
+        let pwm = config.peripherals.pwms.get(name);
+        configs.push(pwm.map(|&pwm| (pwm, config.mixer.rules(name, configuration, &pwm))));
+    }
+    configs
+}
+
+/// Named outputs shared by every aircraft configuration, motors follow arming,
+/// ESC calibration and DShot command, servos slew towards mixed value
+pub struct Outputs {
+    pwms: Vec<Output>,
+    configs: Vec<Option<(PwmConfig, Rules)>>,
+    config_version: u8,
+    arming: Option<Box<dyn StaticData<Arming>>>,
+    esc_calibration: Option<Box<dyn StaticData<EscCalibration>>>,
+    dshot_command: Option<CommandRepeater>,
+    servos: Vec<i16>,
+    time: Duration,
+}
+
+impl Outputs {
+    pub fn new(pwms: Vec<Output>) -> Self {
+        let config_version = config::get().version().wrapping_sub(1);
+        let configs = vec![None; pwms.len()];
+        let servos = vec![0; pwms.len()];
+        let time = get_jiffies();
+        Self {
+            pwms,
+            configs,
+            config_version,
+            arming: None,
+            esc_calibration: None,
+            dshot_command: None,
+            servos,
+            time,
+        }
+    }
+
+    pub fn set_arming(&mut self, arming: Box<dyn StaticData<Arming>>) {
+        self.arming = Some(arming);
+    }
+
+    pub fn set_esc_calibration(&mut self, calibration: Box<dyn StaticData<EscCalibration>>) {
+        self.esc_calibration = Some(calibration);
+    }
+
+    pub fn set_dshot_command(&mut self, command: Box<dyn OptionData<Command>>) {
+        self.dshot_command = Some(CommandRepeater::new(command));
+    }
+
+    /// True if config changed and outputs reloaded
+    pub fn reload_config(&mut self) -> bool {
+        let version = config::get().version();
+        if self.config_version == version {
+            return false;
+        }
+        self.configs = load_configs(&self.pwms);
+        self.config_version = version;
+        true
+    }
+
+    /// Throttle of armed motor given by mixed value of its rules, none keeps motor stopped
+    pub fn output<F>(&mut self, input: &ControlInput, auxiliary: &AuxiliaryInput, throttle: F)
+    where
+        F: Fn(&Motor, i16) -> Option<i16>,
+    {
+        let armed = self.arming.as_mut().map(|a| a.read().armed).unwrap_or(false);
+        let calibration = self.esc_calibration.as_mut().map(|c| c.read()).unwrap_or_default();
+        let command = self.dshot_command.as_mut().map(|c| c.next()).flatten();
+        let now = get_jiffies();
+        let elapsed = now - self.time;
+        self.time = now;
+        for (i, (_, pwm, rate)) in self.pwms.iter_mut().enumerate() {
+            let (config, rules) = match self.configs[i] {
+                Some((config, ref rules)) => (config, rules),
+                None => continue,
+            };
+            let value = evaluate(rules, input, auxiliary);
+            let max_duty = pwm.get_max_duty();
+            let duty = match config {
+                // Motors never spin when disarmed whatever mixed in
+                PwmConfig::Motor(motor) => {
+                    let throttle = if armed { throttle(&motor, value) } else { None };
+                    to_motor_output(max_duty, *rate, &motor, throttle, calibration, command)
+                }
+                PwmConfig::Servo(servo) => {
+                    self.servos[i] = slew(self.servos[i], value, servo.slew_rate, elapsed);
+                    to_servo_pwm_duty(max_duty, *rate, self.servos[i], &servo)
+                }
+            };
+            pwm.set_duty(duty);
+        }
+    }
+}
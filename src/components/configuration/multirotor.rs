@@ -0,0 +1,128 @@
+use alloc::vec::Vec;
+
+use crate::components::event::OnEvent;
+use crate::components::mixer::ControlMixer;
+use crate::config;
+use crate::config::aircraft::Configuration;
+use crate::datastructures::data_source::StaticData;
+use crate::datastructures::input::ControlInput;
+
+use super::Outputs;
+
+// Factors of roll, pitch and yaw per motor index,
+// positive roll rolls right, positive pitch raises nose, positive yaw turns nose right
+
+// rear-right(CW), front-right(CCW), rear-left(CCW), front-left(CW)
+const QUAD_X: [[f32; 3]; 4] =
+    [[-1.0, -1.0, -1.0], [-1.0, 1.0, 1.0], [1.0, -1.0, 1.0], [1.0, 1.0, -1.0]];
+
+// rear(CW), right(CCW), left(CCW), front(CW)
+const QUAD_PLUS: [[f32; 3]; 4] =
+    [[0.0, -1.0, -1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, 1.0], [0.0, 1.0, -1.0]];
+
+// rear-right(CCW), front-right(CCW), rear-left(CW), front-left(CW), right(CW), left(CCW)
+const HEXA: [[f32; 3]; 6] = [
+    [-0.5, -0.866, 1.0],
+    [-0.5, 0.866, 1.0],
+    [0.5, -0.866, -1.0],
+    [0.5, 0.866, -1.0],
+    [-1.0, 0.0, -1.0],
+    [1.0, 0.0, 1.0],
+];
+
+// rear, front-right, front-left, yaw by tilting rear motor with servo
+const TRICOPTER: [[f32; 3]; 3] = [[0.0, -1.333, 0.0], [-1.0, 0.667, 0.0], [1.0, 0.667, 0.0]];
+
+fn motor_matrix(configuration: Configuration) -> &'static [[f32; 3]] {
+    match configuration {
+        Configuration::QuadX => &QUAD_X,
+        Configuration::QuadPlus => &QUAD_PLUS,
+        Configuration::Hexa => &HEXA,
+        Configuration::Tricopter => &TRICOPTER,
+        _ => &[],
+    }
+}
+
+fn to_unit(value: i16) -> f32 {
+    value as f32 / 32768.0
+}
+
+/// Mixes input into motor outputs in [0, 1], corrections are scaled down when they
+/// exceed full motor range, then throttle is shifted to keep every motor within range,
+/// so attitude is still controllable at zero or full throttle
+pub fn mix(matrix: &[[f32; 3]], input: &ControlInput, motors: &mut [f32]) {
+    let (roll, pitch, yaw) = (to_unit(input.roll), to_unit(input.pitch), to_unit(input.yaw));
+    let (mut min, mut max) = (0.0f32, 0.0f32);
+    for (i, factors) in matrix.iter().enumerate().take(motors.len()) {
+        motors[i] = roll * factors[0] + pitch * factors[1] + yaw * factors[2];
+        min = min.min(motors[i]);
+        max = max.max(motors[i]);
+    }
+    let scale = if max - min > 1.0 { 1.0 / (max - min) } else { 1.0 };
+    let throttle = (to_unit(input.throttle) + 1.0) / 2.0;
+    let throttle = throttle.max(-min * scale).min(1.0 - max * scale);
+    for motor in motors.iter_mut() {
+        *motor = (throttle + *motor * scale).max(0.0).min(1.0);
+    }
+}
+
+fn to_throttle(value: f32) -> i16 {
+    (value * u16::MAX as f32) as i32 as i16 ^ i16::MIN
+}
+
+pub struct Multirotor<S> {
+    mixer: ControlMixer<S>,
+    outputs: Outputs,
+    motors: Vec<f32>,
+}
+
+impl<S: StaticData<ControlInput>> Multirotor<S> {
+    pub fn new(mut mixer: ControlMixer<S>, outputs: Outputs) -> Self {
+        // Multirotor is not flyable without stabilizer, which follows stick with full authority
+        mixer.set_stabilizer_limit(100, false);
+        Self { mixer, outputs, motors: Vec::new() }
+    }
+}
+
+impl<S: StaticData<ControlInput>> OnEvent for Multirotor<S> {
+    fn on_event(&mut self) {
+        if self.outputs.reload_config() {
+            self.motors = vec![0.0; motor_matrix(config::get().aircraft.configuration).len()];
+        }
+        let input = self.mixer.mix();
+        let auxiliary = self.mixer.auxiliary();
+        mix(motor_matrix(config::get().aircraft.configuration), &input, &mut self.motors);
+        let motors = &self.motors;
+        self.outputs.output(&input, &auxiliary, |motor, _| {
+            motors.get(motor.index as usize).map(|&value| to_throttle(value))
+        });
+    }
+}
+
+mod test {
+    #[test]
+    fn test_multirotor_mix() {
+        use crate::datastructures::input::ControlInput;
+
+        use super::{mix, to_throttle, QUAD_X};
+
+        let mut motors = [0.0f32; 4];
+        let input = ControlInput { throttle: 0, roll: 0, pitch: 0, yaw: 0 };
+        mix(&QUAD_X, &input, &mut motors);
+        assert_eq!(motors, [0.5; 4]);
+
+        // Roll right at zero throttle, throttle raised to keep authority
+        let input = ControlInput { throttle: i16::MIN, roll: 8192, pitch: 0, yaw: 0 };
+        mix(&QUAD_X, &input, &mut motors);
+        assert_eq!(motors, [0.0, 0.0, 0.5, 0.5]);
+
+        // Saturated roll and yaw scaled down into motor range
+        let input = ControlInput { throttle: i16::MAX, roll: i16::MIN, pitch: 0, yaw: i16::MIN };
+        mix(&QUAD_X, &input, &mut motors);
+        assert_eq!(motors, [1.0, 0.5, 0.0, 0.5]);
+
+        assert_eq!(to_throttle(0.0), i16::MIN);
+        assert_eq!(to_throttle(0.5), -1);
+        assert_eq!(to_throttle(1.0), i16::MAX);
+    }
+}
@@ -1,28 +1,130 @@
+use alloc::boxed::Box;
+use core::time::Duration;
+
+use crate::config::peripherals::pwm::{Motor, Protocol, Servo};
+use crate::datastructures::calibration::EscCalibration;
+use crate::datastructures::data_source::OptionData;
+use crate::drivers::dshot::{self, Command};
+
+// in micro seconds
+fn pulse_range(protocol: Protocol) -> (u64, u64) {
+    match protocol {
+        Protocol::OneShot125 => (125, 250),
+        Protocol::OneShot42 => (42, 84),
+        Protocol::Multishot => (5, 25),
+        _ => (1000, 2000),
+    }
+}
+
+pub fn to_motor_duty(max_duty: u16, rate: u16, protocol: Protocol, value: i16) -> u16 {
+    let (min, max) = pulse_range(protocol);
+    let throttle = (value as i32 + i16::MAX as i32 + 1) as u64;
+    let pulse_ns = min * 1000 + (max - min) * 1000 * throttle / u16::MAX as u64;
+    (max_duty as u64 * rate as u64 * pulse_ns / 1000_000_000) as u16
+}
+
+// [1000us, 2000us] => [0, 65535]
+fn pulse_to_unit(pulse: u16) -> u32 {
+    pulse.min(2000).saturating_sub(1000) as u32 * u16::MAX as u32 / 1000
+}
+
+/// Applies idle, min and max throttle when armed, or min command otherwise,
+/// output spans full pulse range of protocol
+pub fn to_motor_value(motor: &Motor, throttle: Option<i16>) -> i16 {
+    let value = match throttle {
+        Some(throttle) => {
+            let throttle = (throttle as i32 - i16::MIN as i32) as u32;
+            let idle = motor.idle as u32 * u16::MAX as u32 / 100;
+            let throttle = idle + (u16::MAX as u32 - idle) * throttle / u16::MAX as u32;
+            let min = pulse_to_unit(motor.min_throttle);
+            let max = pulse_to_unit(motor.max_throttle).max(min);
+            min + (max - min) * throttle / u16::MAX as u32
+        }
+        None => pulse_to_unit(motor.min_command),
+    };
+    (value as i32 + i16::MIN as i32) as i16
+}
+
 pub fn to_motor_pwm_duty(max_duty: u16, rate: u16, value: i16) -> u16 {
-    let duty_per_ms = max_duty as u32 * rate as u32 / 1000;
-    let throttle = (value as i32 + i16::MAX as i32 + 1) as u32;
-    (duty_per_ms + duty_per_ms * throttle / u16::MAX as u32) as u16
+    to_motor_duty(max_duty, rate, Protocol::PWM, value)
+}
+
+/// Duty of motor output, or encoded frame for digital protocols,
+/// throttle is none when disarmed, then ESC calibration or DShot command takes effect
+pub fn to_motor_output(
+    max_duty: u16,
+    rate: u16,
+    motor: &Motor,
+    throttle: Option<i16>,
+    calibration: EscCalibration,
+    command: Option<Command>,
+) -> u16 {
+    if motor.protocol.dshot_rate().is_some() {
+        return match throttle {
+            Some(_) => {
+                dshot::encode(dshot::to_dshot_throttle(to_motor_value(motor, throttle)), false)
+            }
+            None => command.unwrap_or(Command::MotorStop).frame(),
+        };
+    }
+    let value = match (throttle, calibration) {
+        (None, EscCalibration::Max) => i16::MAX,
+        (None, EscCalibration::Min) => i16::MIN,
+        _ => to_motor_value(motor, throttle),
+    };
+    to_motor_duty(max_duty, rate, motor.protocol, value)
+}
+
+/// Sends each DShot command as many frames as ESC requires
+pub struct CommandRepeater {
+    source: Box<dyn OptionData<Command>>,
+    command: Command,
+    remaining: usize,
+}
+
+impl CommandRepeater {
+    pub fn new(source: Box<dyn OptionData<Command>>) -> Self {
+        Self { source, command: Command::MotorStop, remaining: 0 }
+    }
+
+    pub fn next(&mut self) -> Option<Command> {
+        if let Some(command) = self.source.read() {
+            self.command = command;
+            self.remaining = command.repeat();
+        }
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.command)
+    }
 }
 
 fn angle_to_axis(angle: i8) -> i32 {
     angle as i32 * i16::MAX as i32 / 90
 }
 
-pub fn to_servo_pwm_duty(max_duty: u16, value: i16, min: i8, max: i8, reversed: bool) -> u16 {
-    let base = max_duty / 40; // 0.5ms
-    let range = (max_duty / 10) as u32; // 2.0ms
+pub fn to_servo_pwm_duty(max_duty: u16, rate: u16, value: i16, servo: &Servo) -> u16 {
+    let value = if servo.reversed { -(value as i32) } else { value as i32 };
+    let endpoint = if value < 0 { servo.min_endpoint } else { servo.max_endpoint };
+    let (min, max) = (servo.min_angle, servo.max_angle);
     let offset = angle_to_axis((max + min) / 2);
-    let mut value = if reversed { -value } else { value } as i32 + offset;
-    let max = angle_to_axis(max);
-    if value > max {
-        value = max;
-    }
-    let min = angle_to_axis(min);
-    if value < min {
-        value = min;
+    let value = value * endpoint as i32 / 100 + offset;
+    let value = value.min(angle_to_axis(max)).max(angle_to_axis(min));
+    // [-32768, 32767] => [-1.0ms, 1.0ms]
+    let offset_ns = (value + 32768) as i64 * 2000_000 / u16::MAX as i64 - 1000_000;
+    let pulse_ns = (servo.center as i64 + servo.subtrim as i64) * 1000 + offset_ns;
+    (max_duty as i64 * rate as i64 * pulse_ns.max(0) / 1000_000_000) as u16
+}
+
+/// Moves towards target no faster than slew rate in degrees per second
+pub fn slew(current: i16, target: i16, slew_rate: u16, elapsed: Duration) -> i16 {
+    if slew_rate == 0 {
+        return target;
     }
-    let signed = value as i32 + i16::MAX as i32 + 1; // [-32767, 32767] => [1, 65535]
-    base + (range * (signed as u32) / u16::MAX as u32) as u16
+    let max_delta = (slew_rate as u64 * 32768 * elapsed.as_micros() as u64 / 90 / 1000_000) as i32;
+    let delta = (target as i32 - current as i32).max(-max_delta).min(max_delta);
+    (current as i32 + delta) as i16
 }
 
 mod test {
@@ -36,16 +138,141 @@ mod test {
         assert_eq!(to_motor_pwm_duty(max_duty, 400, 32767), 16000);
     }
 
+    #[test]
+    fn test_to_motor_value() {
+        use super::to_motor_value;
+        use crate::config::peripherals::pwm::Motor;
+
+        let mut motor = Motor::default();
+        assert_eq!(to_motor_value(&motor, None), i16::MIN);
+        assert_eq!(to_motor_value(&motor, Some(i16::MIN)), i16::MIN);
+        assert_eq!(to_motor_value(&motor, Some(0)), 0);
+        assert_eq!(to_motor_value(&motor, Some(i16::MAX)), i16::MAX);
+
+        motor.idle = 50;
+        assert_eq!(to_motor_value(&motor, Some(i16::MIN)), -1);
+        motor.idle = 0;
+        motor.min_command = 980;
+        motor.min_throttle = 1100;
+        motor.max_throttle = 1900;
+        assert_eq!(to_motor_value(&motor, None), i16::MIN); // limited to 1000
+        assert_eq!(to_motor_value(&motor, Some(i16::MIN)), -26215);
+        assert_eq!(to_motor_value(&motor, Some(i16::MAX)), 26213);
+    }
+
+    #[test]
+    fn test_esc_calibration_output() {
+        use super::to_motor_output;
+        use crate::config::peripherals::pwm::{Motor, Protocol};
+        use crate::datastructures::calibration::EscCalibration;
+        use crate::drivers::dshot::Command;
+
+        let mut motor = Motor::default();
+        motor.min_command = 1050;
+        let max_duty = 20000;
+        assert_eq!(to_motor_output(max_duty, 400, &motor, None, EscCalibration::Idle, None), 8399);
+        assert_eq!(to_motor_output(max_duty, 400, &motor, None, EscCalibration::Max, None), 16000);
+        assert_eq!(to_motor_output(max_duty, 400, &motor, None, EscCalibration::Min, None), 8000);
+        let throttle = Some(i16::MIN);
+        assert_eq!(
+            to_motor_output(max_duty, 400, &motor, throttle, EscCalibration::Max, None),
+            8000
+        );
+        motor.protocol = Protocol::DShot600;
+        let frame = Command::MotorStop.frame();
+        assert_eq!(to_motor_output(max_duty, 400, &motor, None, EscCalibration::Max, None), frame);
+        let beep = Some(Command::Beep1);
+        let frame = Command::Beep1.frame();
+        assert_eq!(to_motor_output(max_duty, 400, &motor, None, EscCalibration::Idle, beep), frame);
+        let frame = to_motor_output(max_duty, 400, &motor, throttle, EscCalibration::Idle, beep);
+        assert_ne!(frame, Command::Beep1.frame());
+    }
+
+    #[test]
+    fn test_dshot_command_repeat() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use super::CommandRepeater;
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::DataWriter;
+        use crate::drivers::dshot::Command;
+
+        let command = Rc::new(SingularData::default());
+        let mut repeater = CommandRepeater::new(Box::new(SingularDataSource::new(&command)));
+        assert_eq!(repeater.next(), None);
+        command.write(Command::Beep1);
+        assert_eq!(repeater.next(), Some(Command::Beep1));
+        assert_eq!(repeater.next(), None);
+        command.write(Command::SaveSettings);
+        for _ in 0..6 {
+            assert_eq!(repeater.next(), Some(Command::SaveSettings));
+        }
+        assert_eq!(repeater.next(), None);
+    }
+
+    #[test]
+    fn test_to_motor_oneshot_duty() {
+        use super::to_motor_duty;
+        use crate::config::peripherals::pwm::Protocol;
+
+        let max_duty = 42000; // 84MHz at 2KHz
+        assert_eq!(to_motor_duty(max_duty, 2000, Protocol::OneShot125, -32768), 10500);
+        assert_eq!(to_motor_duty(max_duty, 2000, Protocol::OneShot125, 32767), 21000);
+        let max_duty = 10500; // 84MHz at 8KHz
+        assert_eq!(to_motor_duty(max_duty, 8000, Protocol::OneShot42, -32768), 3528);
+        assert_eq!(to_motor_duty(max_duty, 8000, Protocol::OneShot42, 32767), 7056);
+        let max_duty = 2625; // 84MHz at 32KHz
+        assert_eq!(to_motor_duty(max_duty, 32000, Protocol::Multishot, -32768), 420);
+        assert_eq!(to_motor_duty(max_duty, 32000, Protocol::Multishot, 0), 1260);
+        assert_eq!(to_motor_duty(max_duty, 32000, Protocol::Multishot, 32767), 2100);
+    }
+
     #[test]
     fn test_to_servo_pwm_duty() {
         use super::to_servo_pwm_duty;
+        use crate::config::peripherals::pwm::{Servo, ServoType};
 
         let max_duty = 180 * 10;
         let center = max_duty / 40 + max_duty / 20; // 0.5ms + 1.0ms
-        assert_eq!(to_servo_pwm_duty(max_duty, 0, -90, 90, false), center);
-        assert_eq!(to_servo_pwm_duty(max_duty, -32768, -90, 90, false), center - 90);
-        assert_eq!(to_servo_pwm_duty(max_duty, 32767, -90, 90, false), center + 90);
-        assert_eq!(to_servo_pwm_duty(max_duty, -8192, -90, 90, false), center - 23);
-        assert_eq!(to_servo_pwm_duty(max_duty, 8192, -90, 90, false), center + 22);
+        let servo = Servo::of(ServoType::Aileron);
+        assert_eq!(to_servo_pwm_duty(max_duty, 50, 0, &servo), center);
+        assert_eq!(to_servo_pwm_duty(max_duty, 50, -32768, &servo), center - 90);
+        assert_eq!(to_servo_pwm_duty(max_duty, 50, 32767, &servo), center + 90);
+        assert_eq!(to_servo_pwm_duty(max_duty, 50, -8192, &servo), center - 23);
+        assert_eq!(to_servo_pwm_duty(max_duty, 50, 8192, &servo), center + 22);
+    }
+
+    #[test]
+    fn test_servo_trims_and_endpoints() {
+        use super::to_servo_pwm_duty;
+        use crate::config::peripherals::pwm::{Servo, ServoType};
+
+        let max_duty = 10000; // 1 duty per 0.3us at 333Hz
+        let mut servo = Servo::of(ServoType::Elevator);
+        assert_eq!(to_servo_pwm_duty(max_duty, 333, 0, &servo), 4995);
+        servo.center = 1520;
+        servo.subtrim = -20;
+        assert_eq!(to_servo_pwm_duty(max_duty, 333, 0, &servo), 4995);
+        servo.subtrim = 10;
+        servo.min_endpoint = 50;
+        servo.max_endpoint = 120;
+        assert_eq!(to_servo_pwm_duty(max_duty, 333, i16::MIN, &servo), 3429);
+        assert_eq!(to_servo_pwm_duty(max_duty, 333, i16::MAX, &servo), 8424);
+        servo.max_angle = 45;
+        assert_eq!(to_servo_pwm_duty(max_duty, 333, i16::MAX, &servo), 6759);
+    }
+
+    #[test]
+    fn test_servo_slew() {
+        use core::time::Duration;
+
+        use super::slew;
+
+        let elapsed = Duration::from_millis(20);
+        assert_eq!(slew(0, i16::MAX, 0, elapsed), i16::MAX);
+        assert_eq!(slew(0, i16::MAX, 450, elapsed), 3276);
+        assert_eq!(slew(0, -100, 450, elapsed), -100);
+        assert_eq!(slew(i16::MIN, i16::MAX, 4500, Duration::from_secs(1)), i16::MAX);
     }
 }
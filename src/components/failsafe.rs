@@ -0,0 +1,230 @@
+use alloc::rc::Rc;
+use core::time::Duration;
+
+use crate::config;
+use crate::config::receiver::FailsafeAction;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::DataWriter;
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::datastructures::input::ControlInput;
+
+/// Stages timed from receiver considered stale,
+/// hold last input, then level wings with configured throttle, then take action
+pub struct Failsafe {
+    last_input: ControlInput,
+    stale_since: Option<Duration>,
+    stage: FailsafeStage,
+    output: Rc<SingularData<FailsafeStage>>,
+}
+
+impl Failsafe {
+    pub fn new() -> Self {
+        Self {
+            last_input: ControlInput::default(),
+            stale_since: None,
+            stage: FailsafeStage::Normal,
+            output: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn reader(&self) -> SingularDataSource<FailsafeStage> {
+        SingularDataSource::new(&self.output)
+    }
+
+    pub fn stage(&self) -> FailsafeStage {
+        self.stage
+    }
+
+    fn next_stage(&mut self, input: Option<ControlInput>, now: Duration) -> FailsafeStage {
+        if let Some(input) = input {
+            self.last_input = input;
+            self.stale_since = None;
+            return FailsafeStage::Normal;
+        }
+        let since = *self.stale_since.get_or_insert(now);
+        let elapsed = (now - since).as_millis() as usize;
+        let config = &config::get().receiver.failsafe;
+        if elapsed < config.hold as usize {
+            FailsafeStage::Hold
+        } else if elapsed < config.hold as usize + config.level as usize {
+            FailsafeStage::Level
+        } else {
+            match config.action {
+                FailsafeAction::ReturnToHome => FailsafeStage::ReturnToHome,
+                FailsafeAction::Cut => FailsafeStage::Cut,
+            }
+        }
+    }
+
+    pub fn update(&mut self, input: Option<ControlInput>, now: Duration) -> ControlInput {
+        let stage = self.next_stage(input, now);
+        if stage != self.stage {
+            if stage == FailsafeStage::Normal {
+                info!("Failsafe recovered");
+            } else {
+                warn!("Failsafe stage {}", stage);
+            }
+            self.stage = stage;
+        }
+        self.output.write(stage);
+
+        let throttle = config::get().receiver.failsafe.throttle as i32;
+        let level = ControlInput {
+            throttle: (i16::MIN as i32 + u16::MAX as i32 * throttle / 100) as i16,
+            ..Default::default()
+        };
+        match stage {
+            FailsafeStage::Normal | FailsafeStage::Hold => self.last_input,
+            FailsafeStage::Level | FailsafeStage::ReturnToHome => level,
+            FailsafeStage::Cut => ControlInput::default(),
+        }
+    }
+}
+
+/// Flies armed control stack at 50Hz with RC lost from 1 second until recovered if any,
+/// checks each step with failsafe stage and mixer output, returns stages passed through
+#[cfg(test)]
+pub fn fly_through_rc_loss(
+    autopilot: Option<crate::components::autopilot::Autopilot>,
+    seconds: usize,
+    recovered: Option<usize>,
+    mut check: impl FnMut(usize, FailsafeStage, &ControlInput),
+) -> alloc::vec::Vec<FailsafeStage> {
+    use alloc::boxed::Box;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::components::arming::ArmingControl;
+    use crate::components::mixer::ControlMixer;
+    use crate::components::schedule::Schedulable;
+    use crate::datastructures::arming::PreArmFailures;
+    use crate::datastructures::data_source::StaticData;
+    use crate::datastructures::measurement::battery::Battery;
+    use crate::sys::timer;
+
+    static NOW: AtomicUsize = AtomicUsize::new(0);
+    fn jiffies() -> Duration {
+        Duration::from_millis(NOW.load(Ordering::Relaxed) as u64)
+    }
+    timer::init(jiffies);
+
+    let battery = Rc::new(SingularData::default());
+    battery.write(Battery(16000));
+    let calibrated = Rc::new(SingularData::default());
+    calibrated.write(true);
+    let receiver = Rc::new(SingularData::default());
+    let switch = Rc::new(SingularData::default());
+    let stabilizer: Rc<SingularData<ControlInput>> = Rc::new(SingularData::default());
+
+    let mut arming =
+        ArmingControl::new(SingularDataSource::new(&battery), SingularDataSource::new(&calibrated));
+    arming.set_receiver(
+        Box::new(SingularDataSource::new(&receiver)),
+        Box::new(SingularDataSource::new(&switch)),
+    );
+    let mut armed = arming.reader();
+    arming.update(Some(false), PreArmFailures::default());
+    assert!(arming.update(Some(true), PreArmFailures::default()));
+
+    let receiver_source = Box::new(SingularDataSource::new(&receiver));
+    let mut mixer = ControlMixer::new(receiver_source, 5, SingularDataSource::new(&stabilizer));
+    if let Some(autopilot) = autopilot {
+        mixer.set_autopilot(autopilot);
+    }
+    let mut stage = mixer.failsafe_reader();
+
+    let input = ControlInput { throttle: 1000, roll: 2000, pitch: 3000, yaw: 4000 };
+    let mut stages = vec![FailsafeStage::Normal];
+    for i in 0..seconds * 50 {
+        NOW.store(i * 20, Ordering::Relaxed);
+        if i < 50 || recovered.map(|second| i >= second * 50).unwrap_or(false) {
+            receiver.write(input);
+            switch.write(true);
+        }
+        arming.schedule();
+        let output = mixer.mix();
+        let stage = stage.read();
+        if stages.last() != Some(&stage) {
+            stages.push(stage);
+        }
+        assert!(armed.read().armed);
+        check(i, stage, &output);
+    }
+    timer::init(|| Duration::default());
+    stages
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_failsafe_stages() {
+        use core::time::Duration;
+
+        use crate::components::logger::{self, Level};
+        use crate::config::receiver::FailsafeAction;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::StaticData;
+        use crate::datastructures::failsafe::FailsafeStage;
+        use crate::datastructures::input::ControlInput;
+
+        use super::Failsafe;
+
+        logger::init(Level::Debug);
+        let mut config = Config::default();
+        config.receiver.failsafe.action = FailsafeAction::ReturnToHome;
+        config::replace(config);
+
+        let mut failsafe = Failsafe::new();
+        let mut stage = failsafe.reader();
+        let input = ControlInput { throttle: 1000, roll: 2000, pitch: 3000, yaw: 4000 };
+        let ms = |ms| Duration::from_millis(ms);
+
+        let output = failsafe.update(Some(input), ms(0));
+        assert_eq!((stage.read(), output.roll), (FailsafeStage::Normal, 2000));
+        let output = failsafe.update(None, ms(20));
+        assert_eq!((stage.read(), output.roll), (FailsafeStage::Hold, 2000));
+        let output = failsafe.update(None, ms(519));
+        assert_eq!((stage.read(), output.roll), (FailsafeStage::Hold, 2000));
+        let output = failsafe.update(None, ms(520));
+        assert_eq!(stage.read(), FailsafeStage::Level);
+        assert_eq!((output.throttle, output.roll), (-13108, 0));
+        failsafe.update(None, ms(3520));
+        assert_eq!(stage.read(), FailsafeStage::ReturnToHome);
+        let output = failsafe.update(Some(input), ms(4000));
+        assert_eq!((stage.read(), output.pitch), (FailsafeStage::Normal, 3000));
+
+        let mut config = Config::default();
+        config.receiver.failsafe.action = FailsafeAction::Cut;
+        config::replace(config);
+        failsafe.update(None, ms(5000));
+        let output = failsafe.update(None, ms(9000));
+        assert_eq!((stage.read(), output.throttle), (FailsafeStage::Cut, i16::MIN));
+    }
+
+    #[test]
+    #[serial]
+    fn test_failsafe_while_armed() {
+        use crate::config::{self, Config};
+        use crate::datastructures::failsafe::FailsafeStage;
+
+        use super::fly_through_rc_loss;
+
+        config::replace(Config::default());
+        // RC lost after 1 second and recovered at 6 seconds
+        let stages = fly_through_rc_loss(None, 7, Some(6), |_, stage, output| match stage {
+            FailsafeStage::Normal | FailsafeStage::Hold => {
+                assert_eq!((output.throttle, output.roll), (1000, 2000))
+            }
+            FailsafeStage::Level => assert_eq!((output.throttle, output.roll), (-13108, 0)),
+            FailsafeStage::ReturnToHome => unreachable!(),
+            FailsafeStage::Cut => assert_eq!(output.throttle, i16::MIN),
+        });
+        let expected = [
+            FailsafeStage::Normal,
+            FailsafeStage::Hold,
+            FailsafeStage::Level,
+            FailsafeStage::Cut,
+            FailsafeStage::Normal,
+        ];
+        assert_eq!(&stages[..], &expected[..]);
+    }
+}
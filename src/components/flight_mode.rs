@@ -0,0 +1,175 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{AgingStaticData, DataWriter, StaticData};
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::geofence::Breach;
+use crate::datastructures::input::{AuxiliaryInput, InputType};
+
+pub struct FlightModeControl {
+    receiver: Option<Box<dyn AgingStaticData<FlightMode>>>,
+    failsafe: Option<Box<dyn StaticData<FailsafeStage>>>,
+    auxiliary: Option<Box<dyn StaticData<AuxiliaryInput>>>,
+    geofence: Option<Box<dyn StaticData<Breach>>>,
+    breached: bool,
+    fence: Option<(FlightMode, Option<FlightMode>)>,
+    mode: FlightMode,
+    output: Rc<SingularData<FlightMode>>,
+}
+
+impl FlightModeControl {
+    pub fn new() -> Self {
+        Self {
+            receiver: None,
+            failsafe: None,
+            auxiliary: None,
+            geofence: None,
+            breached: false,
+            fence: None,
+            mode: FlightMode::default(),
+            output: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn set_receiver(&mut self, receiver: Box<dyn AgingStaticData<FlightMode>>) {
+        self.receiver = Some(receiver);
+    }
+
+    pub fn set_failsafe(&mut self, failsafe: Box<dyn StaticData<FailsafeStage>>) {
+        self.failsafe = Some(failsafe);
+    }
+
+    /// Return-to-home switch overrides receiver requested mode
+    pub fn set_auxiliary(&mut self, auxiliary: Box<dyn StaticData<AuxiliaryInput>>) {
+        self.auxiliary = Some(auxiliary);
+    }
+
+    /// Breach action takes over until another mode selected by receiver
+    pub fn set_geofence(&mut self, geofence: Box<dyn StaticData<Breach>>) {
+        self.geofence = Some(geofence);
+    }
+
+    pub fn reader(&self) -> SingularDataSource<FlightMode> {
+        SingularDataSource::new(&self.output)
+    }
+
+    pub fn mode(&self) -> FlightMode {
+        self.mode
+    }
+
+    fn fence(&mut self, requested: Option<FlightMode>, breach: Breach) -> Option<FlightMode> {
+        let breached = breach != Breach::None;
+        if breached && !self.breached {
+            let action = config::get().geofence.action.flight_mode();
+            self.fence = action.map(|mode| (mode, requested));
+        }
+        self.breached = breached;
+        match self.fence {
+            Some((_, origin)) if requested.is_some() && requested != origin => {
+                self.fence = None;
+                requested
+            }
+            Some((mode, _)) => Some(mode),
+            None => requested,
+        }
+    }
+
+    pub fn update(&mut self, requested: Option<FlightMode>, failsafe: FailsafeStage) -> FlightMode {
+        let mode = match failsafe {
+            FailsafeStage::Normal | FailsafeStage::Hold => requested.unwrap_or(self.mode),
+            FailsafeStage::Level | FailsafeStage::Cut => FlightMode::Stabilized,
+            FailsafeStage::ReturnToHome => FlightMode::ReturnToHome,
+        };
+        if mode != self.mode {
+            info!("Flight mode {} => {}", self.mode, mode);
+            self.mode = mode;
+        }
+        self.output.write(mode);
+        mode
+    }
+}
+
+impl Schedulable for FlightModeControl {
+    fn rate(&self) -> Rate {
+        50
+    }
+
+    fn schedule(&mut self) -> bool {
+        let rate = self.rate();
+        let mut requested = self.receiver.as_mut().map(|r| r.read(rate)).flatten();
+        if let Some(InputType::Aux(index)) = config::get().autopilot.rth.switch {
+            let auxiliary = self.auxiliary.as_mut().map(|a| a.read()).unwrap_or_default();
+            if auxiliary.aux[index as usize - 1] > 0 {
+                requested = Some(FlightMode::ReturnToHome);
+            }
+        }
+        let breach = self.geofence.as_mut().map(|g| g.read()).unwrap_or_default();
+        let requested = self.fence(requested, breach);
+        let failsafe = self.failsafe.as_mut().map(|f| f.read()).unwrap_or_default();
+        self.update(requested, failsafe);
+        true
+    }
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_flight_mode_transition() {
+        use crate::components::logger::{self, Level};
+        use crate::datastructures::data_source::StaticData;
+        use crate::datastructures::failsafe::FailsafeStage::{self, Normal};
+        use crate::datastructures::flight_mode::FlightMode;
+
+        use super::FlightModeControl;
+
+        logger::init(Level::Debug);
+        let mut control = FlightModeControl::new();
+        let mut reader = control.reader();
+        assert_eq!(control.update(None, Normal), FlightMode::Manual);
+        assert_eq!(control.update(Some(FlightMode::Acro), Normal), FlightMode::Acro);
+        assert_eq!(reader.read(), FlightMode::Acro);
+        // receiver lost, keep last mode
+        assert_eq!(control.update(None, Normal), FlightMode::Acro);
+        assert_eq!(control.update(Some(FlightMode::Manual), Normal), FlightMode::Manual);
+        assert_eq!(reader.read(), FlightMode::Manual);
+        let mode = control.update(Some(FlightMode::Manual), FailsafeStage::Level);
+        assert_eq!(mode, FlightMode::Stabilized);
+        let mode = control.update(None, FailsafeStage::ReturnToHome);
+        assert_eq!(mode, FlightMode::ReturnToHome);
+        assert_eq!(control.update(Some(FlightMode::Acro), Normal), FlightMode::Acro);
+    }
+
+    #[test]
+    #[serial]
+    fn test_geofence_action() {
+        use crate::config::geofence::BreachAction;
+        use crate::config::{self, Config};
+        use crate::datastructures::flight_mode::FlightMode::{Acro, Loiter, Manual};
+        use crate::datastructures::geofence::Breach;
+
+        use super::FlightModeControl;
+
+        let mut config = Config::default();
+        config.geofence.action = BreachAction::Loiter;
+        config::replace(config);
+
+        let mut control = FlightModeControl::new();
+        assert_eq!(control.fence(Some(Acro), Breach::None), Some(Acro));
+        assert_eq!(control.fence(Some(Acro), Breach::Distance), Some(Loiter));
+        assert_eq!(control.fence(None, Breach::Distance), Some(Loiter));
+        assert_eq!(control.fence(Some(Acro), Breach::None), Some(Loiter));
+        // Pilot takes over and breach not triggered again until cleared
+        assert_eq!(control.fence(Some(Manual), Breach::None), Some(Manual));
+        assert_eq!(control.fence(Some(Manual), Breach::Exclusion), Some(Loiter));
+        assert_eq!(control.fence(Some(Acro), Breach::Exclusion), Some(Acro));
+        assert_eq!(control.fence(Some(Acro), Breach::Exclusion), Some(Acro));
+
+        config::replace(Config::default());
+        let mut control = FlightModeControl::new();
+        assert_eq!(control.fence(Some(Acro), Breach::Altitude), Some(Acro));
+    }
+}
@@ -0,0 +1,298 @@
+use alloc::rc::Rc;
+
+use heapless::consts::U8;
+use heapless::Vec;
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+use nalgebra::Vector2;
+
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::config::geofence::{Geofence as GeofenceConfig, Zone, ZoneType, Zones};
+use crate::config::yaml::YamlParser;
+use crate::datastructures::coordinate::Position;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{DataWriter, StaticData};
+use crate::datastructures::geofence::Breach;
+use crate::datastructures::ins::Validity;
+use crate::datastructures::waypoint::{Home, Steerpoint};
+use crate::hal::io::Read;
+
+// Ray casting towards east, odd number of crossed edges means inside
+fn contains(polygon: &[Vector2<f32>], point: Vector2<f32>) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + polygon.len() - 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+// Horizontal distance in meter
+fn distance(from: Position, to: Position) -> f32 {
+    let delta = to - from;
+    let (x, y) = (delta.x.value() as f32, delta.y.value() as f32);
+    (x * x + y * y).sqrt()
+}
+
+/// Checks position against cylinder around home and polygon zones,
+/// horizontal limits are not checked until home is fixed by GNSS
+/// and while INS horizontal position is invalid, zones with less than 3 points are ignored
+pub struct Geofence<N, H, V> {
+    navigation: N,
+    home: H,
+    validity: V,
+    fence: GeofenceConfig,
+    breach: Breach,
+    output: Rc<SingularData<Breach>>,
+}
+
+impl<N, H, V> Geofence<N, H, V> {
+    pub fn new(navigation: N, home: H, validity: V) -> Self {
+        let mut fence = config::get().geofence.clone();
+        let zones = core::mem::replace(&mut fence.zones, Zones::new());
+        let mut geofence = Self {
+            navigation,
+            home,
+            validity,
+            fence,
+            breach: Breach::None,
+            output: Rc::new(SingularData::default()),
+        };
+        geofence.add_zones(zones.into_iter());
+        geofence
+    }
+
+    fn add_zones(&mut self, zones: impl Iterator<Item = Zone>) {
+        for zone in zones {
+            if !zone.is_polygon() {
+                warn!("Geofence zone with less than 3 points ignored");
+                continue;
+            }
+            if self.fence.zones.push(zone).is_err() {
+                warn!("Too many geofence zones");
+                break;
+            }
+        }
+    }
+
+    pub fn reader(&self) -> SingularDataSource<Breach> {
+        SingularDataSource::new(&self.output)
+    }
+
+    /// Loads fence file in same format as geofence config,
+    /// limits specified override config and zones are appended
+    pub fn load<E>(&mut self, reader: &mut dyn Read<Error = E>) {
+        let mut buffer = [0u8; 2048];
+        let size = reader.read(&mut buffer).ok().unwrap_or(0);
+        let doc = unsafe { core::str::from_utf8_unchecked(&buffer[..size]) };
+        let fence: GeofenceConfig = YamlParser::new(doc).parse();
+        if fence.max_altitude > 0 {
+            self.fence.max_altitude = fence.max_altitude;
+        }
+        if fence.max_distance > 0 {
+            self.fence.max_distance = fence.max_distance;
+        }
+        self.add_zones(fence.zones.into_iter());
+    }
+
+    pub fn check(&self, home: Home, position: Position, validity: Validity) -> Breach {
+        let fence = &self.fence;
+        let height = (position - home.position).z.value();
+        if fence.max_altitude > 0 && home.altitude_set && height > fence.max_altitude as i32 {
+            return Breach::Altitude;
+        }
+        if !home.fixed || !validity.horizontal {
+            return Breach::None;
+        }
+        let distance = distance(home.position, position);
+        if fence.max_distance > 0 && distance > fence.max_distance as f32 {
+            return Breach::Distance;
+        }
+        let mut included = None;
+        for zone in fence.zones.iter() {
+            let polygon: Vec<Vector2<f32>, U8> = zone
+                .points
+                .iter()
+                .map(|&point| {
+                    let point: Position = point.into();
+                    let delta = point - position;
+                    Vector2::new(delta.x.value() as f32, delta.y.value() as f32)
+                })
+                .collect();
+            let inside = contains(&polygon, Vector2::new(0.0, 0.0));
+            match zone.zone_type {
+                ZoneType::Inclusion => *included.get_or_insert(false) |= inside,
+                ZoneType::Exclusion if inside => return Breach::Exclusion,
+                ZoneType::Exclusion => (),
+            }
+        }
+        match included {
+            Some(false) => Breach::Inclusion,
+            _ => Breach::None,
+        }
+    }
+}
+
+impl<N, H, V> Schedulable for Geofence<N, H, V>
+where
+    N: StaticData<(Position, Steerpoint)>,
+    H: StaticData<Home>,
+    V: StaticData<Validity>,
+{
+    fn rate(&self) -> Rate {
+        10
+    }
+
+    fn schedule(&mut self) -> bool {
+        let (position, _) = self.navigation.read();
+        let (home, validity) = (self.home.read(), self.validity.read());
+        let breach = self.check(home, position, validity);
+        if breach != self.breach {
+            if breach == Breach::None {
+                info!("Geofence clear");
+            } else {
+                warn!("Geofence breach {}", breach);
+            }
+            self.breach = breach;
+        }
+        self.output.write(breach);
+        true
+    }
+}
+
+mod test {
+    #[test]
+    fn test_point_in_polygon() {
+        use nalgebra::Vector2;
+
+        use super::contains;
+
+        let square = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(100.0, 0.0),
+            Vector2::new(100.0, 100.0),
+            Vector2::new(0.0, 100.0),
+        ];
+        assert!(contains(&square, Vector2::new(50.0, 50.0)));
+        assert!(!contains(&square, Vector2::new(150.0, 50.0)));
+        assert!(!contains(&square, Vector2::new(-50.0, 50.0)));
+
+        // U shape opening to north
+        let concave = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(300.0, 0.0),
+            Vector2::new(300.0, 300.0),
+            Vector2::new(200.0, 300.0),
+            Vector2::new(200.0, 100.0),
+            Vector2::new(100.0, 100.0),
+            Vector2::new(100.0, 300.0),
+            Vector2::new(0.0, 300.0),
+        ];
+        assert!(contains(&concave, Vector2::new(50.0, 200.0)));
+        assert!(contains(&concave, Vector2::new(250.0, 200.0)));
+        assert!(!contains(&concave, Vector2::new(150.0, 200.0)));
+        assert!(contains(&concave, Vector2::new(150.0, 50.0)));
+        assert!(!contains(&[], Vector2::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_distance() {
+        use crate::datastructures::coordinate::{Latitude, Longitude, Position};
+        use crate::datastructures::measurement::displacement::DistanceVector;
+        use crate::datastructures::measurement::unit::Meter;
+
+        use super::distance;
+
+        let latitude = Latitude::from_str("N22°32.000").unwrap();
+        let longitude = Longitude::from_str("E113°56.000").unwrap();
+        let home = Position { latitude, longitude, ..Default::default() };
+        let position = home + DistanceVector::new(300, -400, 50, Meter);
+        assert!((distance(home, position) - 500.0).abs() < 2.0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_geofence() {
+        use crate::config::geofence::{Point, Zone, ZoneType};
+        use crate::config::{self, Config};
+        use crate::datastructures::coordinate::{Latitude, Longitude, Position};
+        use crate::datastructures::data_source::NoDataSource;
+        use crate::datastructures::geofence::Breach;
+        use crate::datastructures::ins::Validity;
+        use crate::datastructures::measurement::displacement::DistanceVector;
+        use crate::datastructures::measurement::distance::Distance;
+        use crate::datastructures::measurement::unit::{CentiMeter, Meter};
+        use crate::datastructures::waypoint::Home;
+
+        use super::Geofence;
+
+        let latitude = Latitude::from_str("N22°32.000").unwrap();
+        let longitude = Longitude::from_str("E113°56.000").unwrap();
+        let altitude = Distance::new(1000, Meter).to_unit(CentiMeter);
+        let home = Position { latitude, longitude, altitude };
+        let point = |x, y| {
+            let position = home + DistanceVector::new(x, y, 0, Meter);
+            Point { latitude: position.latitude, longitude: position.longitude }
+        };
+
+        let mut config = Config::default();
+        config.geofence.max_altitude = 120;
+        config.geofence.max_distance = 500;
+        let mut inclusion = Zone::default();
+        for &(x, y) in [(-200, -200), (400, -200), (400, 400), (-200, 400)].iter() {
+            inclusion.points.push(point(x, y)).ok();
+        }
+        let mut exclusion = Zone { zone_type: ZoneType::Exclusion, ..Default::default() };
+        for &(x, y) in [(100, 100), (200, 100), (200, 200), (100, 200)].iter() {
+            exclusion.points.push(point(x, y)).ok();
+        }
+        // Line encloses nothing
+        let mut line = Zone::default();
+        for &(x, y) in [(1000, 1000), (2000, 1000)].iter() {
+            line.points.push(point(x, y)).ok();
+        }
+        config.geofence.zones.push(inclusion).ok();
+        config.geofence.zones.push(exclusion).ok();
+        config.geofence.zones.push(line).ok();
+        config::replace(config);
+
+        let geofence = Geofence::new(
+            NoDataSource::<()>::new(),
+            NoDataSource::<()>::new(),
+            NoDataSource::<()>::new(),
+        );
+        let at = |x, y, z| home + DistanceVector::new(x, y, z, Meter);
+        let fixed = Home { position: home, altitude_set: true, fixed: true };
+        let valid = Validity { horizontal: true, vertical: true, velocity: true };
+        assert_eq!(geofence.check(fixed, at(0, 0, 50), valid), Breach::None);
+        assert_eq!(geofence.check(fixed, at(0, 0, 150), valid), Breach::Altitude);
+        assert_eq!(geofence.check(fixed, at(-300, 0, 50), valid), Breach::Inclusion);
+        assert_eq!(geofence.check(fixed, at(150, 150, 50), valid), Breach::Exclusion);
+        assert_eq!(geofence.check(fixed, at(380, 380, 50), valid), Breach::Distance);
+
+        // Horizontal limits ignored without GNSS fixed home
+        let unfixed = Home { fixed: false, ..fixed };
+        assert_eq!(geofence.check(unfixed, at(-300, 0, 50), valid), Breach::None);
+        assert_eq!(geofence.check(unfixed, at(0, 0, 150), valid), Breach::Altitude);
+        let unset = Home::default();
+        assert_eq!(geofence.check(unset, at(0, 0, 150), valid), Breach::None);
+
+        // Horizontal limits ignored while INS horizontal position invalid
+        let invalid = Validity { horizontal: false, ..valid };
+        assert_eq!(geofence.check(fixed, at(-300, 0, 50), invalid), Breach::None);
+        assert_eq!(geofence.check(fixed, at(0, 0, 150), invalid), Breach::Altitude);
+
+        // Home at sea level on equator
+        let home = Position { longitude, ..Default::default() };
+        let fixed = Home { position: home, altitude_set: true, fixed: true };
+        let at = |x, y, z| home + DistanceVector::new(x, y, z, Meter);
+        assert_eq!(geofence.check(fixed, at(0, 0, 150), valid), Breach::Altitude);
+        assert_eq!(geofence.check(fixed, at(0, 600, 50), valid), Breach::Distance);
+    }
+}
@@ -5,15 +5,90 @@ use alloc::rc::Rc;
 use micromath::F32Ext;
 use nalgebra::{Quaternion, UnitQuaternion, Vector3};
 
+use crate::algorithm::ekf::EKF;
+use crate::algorithm::madgwick::Madgwick;
 use crate::algorithm::mahony::{MagnetismOrHeading as Heading, Mahony};
 use crate::components::schedule::{Rate, Schedulable};
 use crate::config;
+use crate::config::imu::AHRS;
 use crate::datastructures::data_source::overwriting::{OverwritingData, OverwritingDataSource};
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
 use crate::datastructures::data_source::{
     AgingStaticData, DataWriter, OptionData, StaticData, WithCapacity,
 };
 use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
-use crate::datastructures::measurement::{Acceleration, Axes, Gyro, HeadingOrCourse, Magnetism};
+use crate::datastructures::measurement::unit::Meter;
+use crate::datastructures::measurement::{
+    Acceleration, Axes, Gyro, HeadingOrCourse, Magnetism, VelocityVector, GRAVITY,
+};
+
+enum Estimator {
+    Mahony(Mahony),
+    Madgwick(Madgwick),
+    EKF(EKF),
+}
+
+impl Estimator {
+    fn new(sample_rate: f32) -> Self {
+        let config = &config::get().imu;
+        let declination = config.magnetometer.declination.into();
+        match config.ahrs {
+            AHRS::Mahony => {
+                let (kp, ki) = (config.mahony.kp.into(), config.mahony.ki.into());
+                Self::Mahony(Mahony::new(sample_rate, kp, ki, declination))
+            }
+            AHRS::Madgwick => {
+                Self::Madgwick(Madgwick::new(sample_rate, config.madgwick.beta.into(), declination))
+            }
+            AHRS::EKF => {
+                let noise = &config.ekf;
+                let mut ekf = EKF::new(sample_rate, declination);
+                let gyro_noise: f32 = noise.gyro_noise.into();
+                let gyro_bias_noise: f32 = noise.gyro_bias_noise.into();
+                ekf.set_process_noise(
+                    gyro_noise / DEGREE_PER_DAG,
+                    gyro_bias_noise / DEGREE_PER_DAG,
+                );
+                let heading_noise = noise.heading_noise as f32 / DEGREE_PER_DAG;
+                ekf.set_measurement_noise(noise.accel_noise.into(), heading_noise);
+                Self::EKF(ekf)
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        gyro: &Vector3<f32>,
+        acceleration: &Vector3<f32>,
+        heading: Option<Heading>,
+    ) -> Option<UnitQuaternion<f32>> {
+        match self {
+            Self::Mahony(mahony) => mahony.update(gyro, acceleration, heading),
+            Self::Madgwick(madgwick) => madgwick.update(gyro, acceleration, heading),
+            Self::EKF(ekf) => ekf.update(gyro, acceleration, heading),
+        }
+    }
+}
+
+/// Centripetal acceleration in g and body frame,
+/// from earth frame yaw rate and velocity in m/s
+fn centripetal(
+    attitude: &UnitQuaternion<f32>,
+    gyro: &Vector3<f32>,
+    velocity: &Vector3<f32>,
+) -> Vector3<f32> {
+    let yaw_rate = attitude.transform_vector(gyro)[2];
+    let centripetal = Vector3::new(-yaw_rate * velocity[1], yaw_rate * velocity[0], 0.0);
+    attitude.inverse_transform_vector(&(centripetal / GRAVITY))
+}
+
+// Full trust at 1g, no trust once deviation reaches max deviation
+fn accel_trust(magnitude: f32, max_deviation: f32) -> f32 {
+    if max_deviation <= 0.0 {
+        return 1.0;
+    }
+    (1.0 - (magnitude - 1.0).abs() / max_deviation).max(0.0)
+}
 
 pub struct IMU<A, G> {
     accelerometer: A,
@@ -21,8 +96,13 @@ pub struct IMU<A, G> {
 
     magnetometer: Option<Box<dyn StaticData<Magnetism>>>,
     gnss: Option<Box<dyn AgingStaticData<HeadingOrCourse>>>,
+    speedometer: Option<Box<dyn StaticData<VelocityVector<f32, Meter>>>>,
 
-    ahrs: Mahony,
+    ahrs: Estimator,
+    centripetal: bool,
+    max_accel_deviation: f32,
+    velocity: Option<Vector3<f32>>,
+    attitude: UnitQuaternion<f32>,
     accel_bias: Axes,
     accel_gain: Axes,
     gyro_bias: Axes,
@@ -31,7 +111,9 @@ pub struct IMU<A, G> {
     calibration_loop: u16,
     counter: usize,
     calibrated: bool,
+    calibrated_output: Rc<SingularData<bool>>,
     acceleration: Rc<OverwritingData<Vector3<f32>>>,
+    gyro: Rc<OverwritingData<Vector3<f32>>>,
     quaternion: Rc<OverwritingData<UnitQuaternion<f32>>>,
 }
 
@@ -39,17 +121,22 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
     pub fn new(accelerometer: A, gyroscope: G, sample_rate: u16) -> Self {
         let size = accelerometer.capacity();
         let acceleration = Vector3::<f32>::new(0.0, 0.0, 0.0);
+        let gyro = Vector3::<f32>::new(0.0, 0.0, 0.0);
         let unit = UnitQuaternion::new_normalize(Quaternion::<f32>::new(1.0, 0.0, 0.0, 0.0));
         let config = &config::get().imu;
-        let (kp, ki) = (config.mahony.kp.into(), config.mahony.ki.into());
         Self {
             accelerometer,
             gyroscope,
 
             magnetometer: None,
             gnss: None,
+            speedometer: None,
 
-            ahrs: Mahony::new(sample_rate as f32, kp, ki, config.magnetometer.declination.into()),
+            ahrs: Estimator::new(sample_rate as f32),
+            centripetal: config.compensation.centripetal,
+            max_accel_deviation: config.compensation.max_accel_deviation.into(),
+            velocity: None,
+            attitude: unit,
             accel_bias: config.accelerometer.bias.into(),
             accel_gain: config.accelerometer.gain.into(),
             gyro_bias: Default::default(),
@@ -58,7 +145,9 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
             calibration_loop: 50,
             counter: 0,
             calibrated: false,
+            calibrated_output: Rc::new(SingularData::default()),
             acceleration: Rc::new(OverwritingData::new(vec![acceleration; size])),
+            gyro: Rc::new(OverwritingData::new(vec![gyro; size])),
             quaternion: Rc::new(OverwritingData::new(vec![unit; size])),
         }
     }
@@ -71,6 +160,14 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
         self.gnss = Some(gnss);
     }
 
+    /// Velocity for centripetal acceleration compensation
+    pub fn set_speedometer(
+        &mut self,
+        speedometer: Box<dyn StaticData<VelocityVector<f32, Meter>>>,
+    ) {
+        self.speedometer = Some(speedometer);
+    }
+
     pub fn set_calibration_loop(&mut self, value: u16) {
         self.calibration_loop = value;
     }
@@ -79,6 +176,14 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
         OverwritingDataSource::new(&self.acceleration)
     }
 
+    pub fn calibrated_reader(&self) -> SingularDataSource<bool> {
+        SingularDataSource::new(&self.calibrated_output)
+    }
+
+    pub fn as_gyroscope(&self) -> OverwritingDataSource<Vector3<f32>> {
+        OverwritingDataSource::new(&self.gyro)
+    }
+
     pub fn reader(&self) -> OverwritingDataSource<UnitQuaternion<f32>> {
         OverwritingDataSource::new(&self.quaternion)
     }
@@ -105,6 +210,7 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
         self.gyro_bias = (self.gyro_bias + bias) / 2;
         self.counter += 1;
         self.calibrated = self.counter >= self.calibration_loop as usize;
+        self.calibrated_output.write(self.calibrated);
         true
     }
 
@@ -115,11 +221,26 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> IMU<A, G>
         let acceleration: Vector3<f32> = acceleration.0.into();
         let mut gyro: Vector3<f32> = raw_gyro.into();
         gyro = gyro / DEGREE_PER_DAG;
+        self.gyro.write(gyro);
 
-        if let Some(quaternion) = self.ahrs.update(&gyro, &acceleration, heading) {
+        // Accelerometer reads gravity direction minus acceleration
+        let attitude = self.attitude;
+        let mut reference = acceleration;
+        if let Some(velocity) = self.velocity.filter(|_| self.centripetal) {
+            reference += centripetal(&attitude, &gyro, &velocity);
+        }
+        let trust = accel_trust(reference.norm(), self.max_accel_deviation);
+        if trust < 1.0 {
+            // Fades towards estimated gravity direction which has no correction effect
+            let down = attitude.inverse_transform_vector(&Vector3::new(0.0, 0.0, -1.0));
+            reference = reference.try_normalize(0.0).unwrap_or(down) * trust + down * (1.0 - trust);
+        }
+
+        if let Some(quaternion) = self.ahrs.update(&gyro, &reference, heading) {
             let acceleration = quaternion.transform_vector(&acceleration);
             self.acceleration.write(acceleration);
             self.quaternion.write(quaternion);
+            self.attitude = quaternion;
         }
     }
 }
@@ -142,6 +263,9 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> Schedulabl
         } else {
             None
         };
+        if let Some(speedometer) = self.speedometer.as_mut() {
+            self.velocity = Some(speedometer.read().into());
+        }
         while let Some(gyro) = self.gyroscope.read() {
             let acceleration = self.accelerometer.read().unwrap();
             self.update_imu(&acceleration, &gyro, heading);
@@ -149,3 +273,59 @@ impl<A: OptionData<Acceleration> + WithCapacity, G: OptionData<Gyro>> Schedulabl
         true
     }
 }
+
+mod test {
+    #[test]
+    fn test_centripetal_compensation() {
+        #[allow(unused_imports)] // false warning
+        use micromath::F32Ext;
+        use nalgebra::Vector3;
+
+        use crate::algorithm::mahony::{MagnetismOrHeading, Mahony};
+        use crate::algorithm::rotation;
+        use crate::datastructures::measurement::euler::Euler;
+        use crate::datastructures::measurement::GRAVITY;
+
+        use super::centripetal;
+
+        // Coordinated right turn at 20 m/s with 30 degree bank
+        let (speed, bank) = (20.0, 30.0);
+        let turn_rate = GRAVITY * (bank as f32).to_radians().tan() / speed;
+        let rate = Vector3::new(0.0, 0.0, -turn_rate);
+        let mut compensated = Mahony::new(100.0, 0.25, 0.005, 0.0);
+        let mut uncompensated = Mahony::new(100.0, 0.25, 0.005, 0.0);
+        let mut heading: f32 = 0.0;
+        let mut attitude = rotation(Vector3::y(), bank);
+        for _ in 0..60 * 100 {
+            attitude = rotation(-Vector3::z(), heading) * rotation(Vector3::y(), bank);
+            let rad = heading.to_radians();
+            let velocity = Vector3::new(rad.sin(), rad.cos(), 0.0) * speed;
+            let gyro = attitude.inverse_transform_vector(&rate);
+            let acceleration = rate.cross(&velocity) / GRAVITY;
+            let down = Vector3::new(0.0, 0.0, -1.0);
+            let accel = attitude.inverse_transform_vector(&(down - acceleration));
+            let course = Some(MagnetismOrHeading::Heading(heading));
+
+            uncompensated.update(&gyro, &accel, course);
+            let reference = accel + centripetal(&compensated.quaternion(), &gyro, &velocity);
+            compensated.update(&gyro, &reference, course);
+            heading = (heading + turn_rate.to_degrees() / 100.0) % 360.0;
+        }
+        let expected = Euler::from(attitude).roll;
+        let roll = Euler::from(compensated.quaternion()).roll;
+        assert!((roll - expected).abs() < 2.0_f32.to_radians());
+        let roll = Euler::from(uncompensated.quaternion()).roll;
+        assert!((roll - expected).abs() > 10.0_f32.to_radians());
+    }
+
+    #[test]
+    fn test_accel_trust() {
+        use super::accel_trust;
+
+        assert_eq!(accel_trust(1.0, 0.3), 1.0);
+        assert!((accel_trust(1.15, 0.3) - 0.5).abs() < 0.001);
+        assert!((accel_trust(0.85, 0.3) - 0.5).abs() < 0.001);
+        assert_eq!(accel_trust(1.5, 0.3), 0.0);
+        assert_eq!(accel_trust(1.5, 0.0), 1.0);
+    }
+}
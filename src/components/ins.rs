@@ -0,0 +1,212 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use nalgebra::Vector3;
+
+use crate::algorithm::ins::INS;
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::datastructures::coordinate::{Displacement, Position};
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{DataWriter, OptionData, StaticData};
+use crate::datastructures::ins::Validity;
+use crate::datastructures::measurement::unit::{CentiMeter, Meter, MilliMeter};
+use crate::datastructures::measurement::{Altitude, VelocityVector, GRAVITY};
+
+const MAX_HORIZONTAL_VARIANCE: f32 = 100.0; // m²
+const MAX_VERTICAL_VARIANCE: f32 = 25.0; // m²
+const MAX_VELOCITY_VARIANCE: f32 = 1.0; // (m/s)²
+
+/// Fuses earth frame acceleration from IMU, barometer altitude and GNSS
+/// into position and velocity, horizontal origin is the first GNSS fix
+pub struct InertialNavigation<A, ACCEL> {
+    altimeter: A,
+    accelerometer: ACCEL,
+
+    gnss_position: Option<Box<dyn OptionData<Position>>>,
+    gnss_velocity: Option<Box<dyn OptionData<VelocityVector<i32, MilliMeter>>>>,
+
+    ins: INS,
+    acceleration: Vector3<f32>,
+    origin: Position,
+    velocity: Rc<SingularData<VelocityVector<f32, Meter>>>,
+    position: Rc<SingularData<Position>>,
+    validity: Rc<SingularData<Validity>>,
+}
+
+impl<A, ACCEL> InertialNavigation<A, ACCEL> {
+    pub fn new(altimeter: A, accelerometer: ACCEL) -> Self {
+        let config = &config::get().ins;
+        let mut ins = INS::new();
+        ins.set_process_noise(config.accel_noise.into(), config.accel_bias_noise.into());
+        let position_noise = config.gnss_position_noise as f32;
+        let velocity_noise = config.gnss_velocity_noise.into();
+        ins.set_measurement_noise(position_noise, velocity_noise, config.baro_noise.into());
+        Self {
+            altimeter,
+            accelerometer,
+            gnss_position: None,
+            gnss_velocity: None,
+            ins,
+            acceleration: Vector3::new(0.0, 0.0, 0.0),
+            origin: Position::default(),
+            velocity: Rc::new(SingularData::default()),
+            position: Rc::new(SingularData::default()),
+            validity: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn set_gnss(
+        &mut self,
+        position: Box<dyn OptionData<Position>>,
+        velocity: Box<dyn OptionData<VelocityVector<i32, MilliMeter>>>,
+    ) {
+        self.gnss_position = Some(position);
+        self.gnss_velocity = Some(velocity);
+    }
+
+    pub fn reader(&self) -> SingularDataSource<VelocityVector<f32, Meter>> {
+        SingularDataSource::new(&self.velocity)
+    }
+
+    pub fn position_reader(&self) -> SingularDataSource<Position> {
+        SingularDataSource::new(&self.position)
+    }
+
+    pub fn validity_reader(&self) -> SingularDataSource<Validity> {
+        SingularDataSource::new(&self.validity)
+    }
+
+    fn validity(&self) -> Validity {
+        let position = self.ins.position_variance();
+        let velocity = self.ins.velocity_variance();
+        Validity {
+            horizontal: self.origin.latitude.0 != 0
+                && position[0].max(position[1]) < MAX_HORIZONTAL_VARIANCE,
+            vertical: !self.origin.altitude.is_zero() && position[2] < MAX_VERTICAL_VARIANCE,
+            velocity: velocity.max() < MAX_VELOCITY_VARIANCE,
+        }
+    }
+}
+
+impl<A, ACCEL> Schedulable for InertialNavigation<A, ACCEL>
+where
+    A: StaticData<Altitude>,
+    ACCEL: OptionData<Vector3<f32>>,
+{
+    fn rate(&self) -> Rate {
+        50
+    }
+
+    fn schedule(&mut self) -> bool {
+        let dt = 1.0 / self.rate() as f32;
+
+        let mut sum = Vector3::new(0.0, 0.0, 0.0);
+        let mut count = 0;
+        while let Some(a) = self.accelerometer.read() {
+            sum += a;
+            count += 1;
+        }
+        if count > 0 {
+            // Accelerometer reads gravity direction minus acceleration
+            self.acceleration = -sum / count as f32 * GRAVITY;
+            self.acceleration[2] -= GRAVITY;
+        }
+        self.ins.predict(&self.acceleration, dt);
+
+        let altitude = self.altimeter.read();
+        if self.origin.altitude.is_zero() {
+            self.origin.altitude = altitude;
+        }
+        let height = (altitude - self.origin.altitude).convert(|v| v as f32).to_unit(Meter);
+        self.ins.correct_altitude(height.value());
+
+        if let Some(position) = self.gnss_position.as_mut().map(|gnss| gnss.read()).flatten() {
+            if self.origin.latitude.0 == 0 {
+                self.origin.latitude = position.latitude;
+                self.origin.longitude = position.longitude;
+                self.ins.reset_position(0.0, 0.0);
+            } else {
+                let displacement = position - self.origin;
+                let (x, y) = (displacement.x.value() as f32, displacement.y.value() as f32);
+                self.ins.correct_position(x, y);
+            }
+        }
+        if let Some(velocity) = self.gnss_velocity.as_mut().map(|gnss| gnss.read()).flatten() {
+            let velocity = velocity.convert(|v| v as f32).to_unit(Meter);
+            self.ins.correct_velocity(velocity.x.value(), velocity.y.value());
+        }
+
+        let v = self.ins.velocity();
+        self.velocity.write(VelocityVector::new(v[0], v[1], v[2], Meter));
+        let p = self.ins.position() * 100.0;
+        let (x, y, z) = (p[0] as i32, p[1] as i32, p[2] as i32);
+        self.position.write(self.origin + Displacement::new(x, y, z, CentiMeter));
+        self.validity.write(self.validity());
+        true
+    }
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_inertial_navigation() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use nalgebra::Vector3;
+
+        use crate::components::schedule::Schedulable;
+        use crate::config::{self, Config};
+        use crate::datastructures::coordinate::{Displacement, Latitude, Longitude, Position};
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::{DataWriter, StaticData};
+        use crate::datastructures::measurement::distance::Distance;
+        use crate::datastructures::measurement::unit::{CentiMeter, Meter, MilliMeter};
+        use crate::datastructures::measurement::VelocityVector;
+
+        use super::InertialNavigation;
+
+        config::replace(Config::default());
+        let altimeter = Rc::new(SingularData::default());
+        let accelerometer = Rc::new(SingularData::default());
+        let gnss_position = Rc::new(SingularData::default());
+        let gnss_velocity = Rc::new(SingularData::default());
+        let mut ins = InertialNavigation::new(
+            SingularDataSource::new(&altimeter),
+            SingularDataSource::new(&accelerometer),
+        );
+        ins.set_gnss(
+            Box::new(SingularDataSource::new(&gnss_position)),
+            Box::new(SingularDataSource::new(&gnss_velocity)),
+        );
+        let mut velocity_reader = ins.reader();
+        let mut position_reader = ins.position_reader();
+        let mut validity_reader = ins.validity_reader();
+
+        // Level flight towards east at 10 m/s
+        let latitude = Latitude::from_str("N22°32.100").unwrap();
+        let longitude = Longitude::from_str("E113°56.200").unwrap();
+        let altitude = Distance::new(1000, Meter).to_unit(CentiMeter);
+        let origin = Position { latitude, longitude, altitude };
+        for i in 0..60 * 50 {
+            accelerometer.write(Vector3::new(0.0, 0.0, -1.0));
+            let current = origin + Displacement::new(i / 5, 0, 0, Meter);
+            altimeter.write(current.altitude);
+            if i % 10 == 0 {
+                gnss_position.write(current);
+                gnss_velocity.write(VelocityVector::new(10_000, 0, 0, MilliMeter));
+            }
+            ins.schedule();
+        }
+        let velocity = velocity_reader.read();
+        assert!((velocity.x.value() - 10.0).abs() < 0.2);
+        assert!(velocity.y.value().abs() < 0.2 && velocity.z.value().abs() < 0.2);
+        let expected = origin + Displacement::new(600, 0, 0, Meter);
+        let error = position_reader.read() - expected;
+        assert!(error.x.value().abs() <= 3 && error.y.value().abs() <= 1);
+        assert_eq!(error.z.value(), 0);
+        let validity = validity_reader.read();
+        assert!(validity.horizontal && validity.vertical && validity.velocity);
+    }
+}
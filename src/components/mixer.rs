@@ -1,14 +1,26 @@
 use alloc::boxed::Box;
+use alloc::rc::Rc;
 
-use crate::datastructures::data_source::{AgingStaticData, StaticData};
+use crate::components::autopilot::Autopilot;
+use crate::components::failsafe::Failsafe;
+use crate::config::mixer::Rule;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{AgingStaticData, DataWriter, StaticData};
+use crate::datastructures::failsafe::FailsafeStage;
 use crate::datastructures::input::ControlInput as Input;
+use crate::datastructures::input::{AuxiliaryInput, Flaps, InputType, LandingGear};
+use crate::sys::timer::get_jiffies;
 
 pub struct ControlMixer<S> {
     receiver: Box<dyn AgingStaticData<Input>>,
     receiver_max_age: usize,
+    failsafe: Failsafe,
     stabilizer: S,
     stablizer_limit: u8,
-    // TODO: autopilot
+    stabilizer_assist: bool,
+    auxiliary: Option<Box<dyn StaticData<AuxiliaryInput>>>,
+    autopilot: Option<Autopilot>,
+    setpoint: Rc<SingularData<Input>>,
 }
 
 fn limit_i16(value: i16, limit: u8) -> i16 {
@@ -23,28 +35,154 @@ fn limit_i16(value: i16, limit: u8) -> i16 {
     }
 }
 
+// Assisting stabilizer only applies when opposing stick deflection,
+// otherwise stabilizer output replaces stick input which is already its setpoint
+fn stabilize(input: i16, stabilizer: i16, limit: u8, assist: bool) -> i16 {
+    if !assist {
+        return limit_i16(stabilizer, limit);
+    }
+    if input > 0 && stabilizer < 0 || input < 0 && stabilizer > 0 {
+        return input.saturating_add(limit_i16(stabilizer, limit));
+    }
+    input
+}
+
+fn input_value(input_type: InputType, input: &Input, auxiliary: &AuxiliaryInput) -> i32 {
+    let value = match input_type {
+        InputType::Throttle => input.throttle,
+        InputType::Roll => input.roll,
+        InputType::Pitch => input.pitch,
+        InputType::Yaw => input.yaw,
+        InputType::Flaps => match auxiliary.fixed_wing.flaps {
+            Flaps::Auto => i16::MIN,
+            Flaps::Half => 0,
+            Flaps::Full => i16::MAX,
+        },
+        InputType::Gear => match auxiliary.fixed_wing.landing_gear {
+            LandingGear::Up => i16::MAX,
+            LandingGear::Down => i16::MIN,
+        },
+        InputType::Aux(index) => auxiliary.aux[index as usize - 1],
+        InputType::Mode | InputType::Arm => 0,
+    };
+    value as i32
+}
+
+/// Sums up weighted inputs of output, each rule clamped by its own limits
+pub fn evaluate(rules: &[Rule], input: &Input, auxiliary: &AuxiliaryInput) -> i16 {
+    let mut sum = 0i32;
+    for rule in rules.iter() {
+        let value = input_value(rule.input, input, auxiliary) * rule.weight as i32 / 100;
+        let value = value + i16::MAX as i32 * rule.offset as i32 / 100;
+        let min = -(i16::MIN as i32) * rule.min as i32 / 100;
+        let max = i16::MAX as i32 * rule.max as i32 / 100;
+        sum += value.max(min).min(max);
+    }
+    sum.max(i16::MIN as i32).min(i16::MAX as i32) as i16
+}
+
 impl<S: StaticData<Input>> ControlMixer<S> {
     pub fn new(receiver: Box<dyn AgingStaticData<Input>>, age: usize, stabilizer: S) -> Self {
-        Self { receiver, receiver_max_age: age, stabilizer, stablizer_limit: 30 }
+        let failsafe = Failsafe::new();
+        let limit = 30;
+        Self {
+            receiver,
+            receiver_max_age: age,
+            failsafe,
+            stabilizer,
+            stablizer_limit: limit,
+            stabilizer_assist: true,
+            auxiliary: None,
+            autopilot: None,
+            setpoint: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn set_auxiliary(&mut self, auxiliary: Box<dyn StaticData<AuxiliaryInput>>) {
+        self.auxiliary = Some(auxiliary);
+    }
+
+    pub fn auxiliary(&mut self) -> AuxiliaryInput {
+        self.auxiliary.as_mut().map(|a| a.read()).unwrap_or_default()
+    }
+
+    pub fn set_autopilot(&mut self, autopilot: Autopilot) {
+        self.autopilot = Some(autopilot);
     }
 
-    pub fn set_stabilizer_limit(&mut self, limit: u8) {
+    pub fn failsafe_reader(&self) -> SingularDataSource<FailsafeStage> {
+        self.failsafe.reader()
+    }
+
+    /// Input after failsafe and autopilot, as setpoint of stabilizer
+    pub fn setpoint_reader(&self) -> SingularDataSource<Input> {
+        SingularDataSource::new(&self.setpoint)
+    }
+
+    /// Stabilizer output limited in percentage, assisting stabilizer only opposes stick input,
+    /// otherwise stabilizer output is used directly
+    pub fn set_stabilizer_limit(&mut self, limit: u8, assist: bool) {
         self.stablizer_limit = limit;
+        self.stabilizer_assist = assist;
     }
 
     pub fn mix(&mut self) -> Input {
-        let mut input = self.receiver.read(self.receiver_max_age).unwrap_or_default();
+        let now = get_jiffies();
+        let input = self.receiver.read(self.receiver_max_age);
+        let mut input = self.failsafe.update(input, now);
+        let auxiliary = self.auxiliary();
+        if let Some(ref mut autopilot) = self.autopilot {
+            input = autopilot.update(input, &auxiliary, self.failsafe.stage(), now);
+        }
+        self.setpoint.write(input);
         let stabilizer = self.stabilizer.read();
 
-        if input.roll > 0 && stabilizer.roll < 0 || input.roll < 0 && stabilizer.roll > 0 {
-            input.roll += limit_i16(stabilizer.roll, self.stablizer_limit);
-        }
-        if input.pitch > 0 && stabilizer.pitch < 0 || input.pitch < 0 && stabilizer.pitch > 0 {
-            input.pitch += limit_i16(stabilizer.pitch, self.stablizer_limit);
-        }
-        if input.yaw > 0 && stabilizer.yaw < 0 || input.yaw < 0 && stabilizer.yaw > 0 {
-            input.yaw += limit_i16(stabilizer.yaw, self.stablizer_limit);
-        }
+        let (limit, assist) = (self.stablizer_limit, self.stabilizer_assist);
+        input.roll = stabilize(input.roll, stabilizer.roll, limit, assist);
+        input.pitch = stabilize(input.pitch, stabilizer.pitch, limit, assist);
+        input.yaw = stabilize(input.yaw, stabilizer.yaw, limit, assist);
         input
     }
 }
+
+mod test {
+    #[test]
+    fn test_evaluate_rules() {
+        use crate::config::mixer::Rule;
+        use crate::datastructures::input::{AuxiliaryInput, ControlInput, Flaps, InputType};
+
+        use super::evaluate;
+
+        let input = ControlInput { throttle: 0, roll: 16384, pitch: 32767, yaw: 0 };
+        let mut auxiliary = AuxiliaryInput::default();
+        let elevon = [Rule::new(InputType::Roll, -100), Rule::new(InputType::Pitch, 100)];
+        assert_eq!(evaluate(&elevon, &input, &auxiliary), 16383);
+        let elevon = [Rule::new(InputType::Roll, 100), Rule::new(InputType::Pitch, 100)];
+        assert_eq!(evaluate(&elevon, &input, &auxiliary), i16::MAX);
+
+        // differential aileron with flaperon
+        let mut aileron = Rule::new(InputType::Roll, 100);
+        aileron.min = -50;
+        let input = ControlInput { throttle: 0, roll: i16::MIN, pitch: 0, yaw: 0 };
+        let rules = [aileron, Rule::new(InputType::Flaps, 25)];
+        auxiliary.fixed_wing.flaps = Flaps::Half;
+        assert_eq!(evaluate(&rules, &input, &auxiliary), -16384);
+        auxiliary.fixed_wing.flaps = Flaps::Full;
+        assert_eq!(evaluate(&rules, &input, &auxiliary), -16384 + 8191);
+    }
+
+    #[test]
+    fn test_stabilize() {
+        use super::stabilize;
+
+        // Fixed wing stabilizer assists pilot within 30% only against stick deflection
+        assert_eq!(stabilize(1000, 20000, 30, true), 1000);
+        assert_eq!(stabilize(1000, -20000, 30, true), 1000 - 9830);
+        assert_eq!(stabilize(-1000, 20000, 30, true), -1000 + 9830);
+        assert_eq!(stabilize(0, 20000, 30, true), 0);
+        // Multirotor stabilizer output follows stick setpoint with full authority
+        assert_eq!(stabilize(1000, 20000, 100, false), 20000);
+        assert_eq!(stabilize(i16::MAX, -1000, 100, false), -1000);
+        assert_eq!(stabilize(0, i16::MIN, 100, false), i16::MIN);
+    }
+}
@@ -1,17 +1,23 @@
 #[macro_use]
 pub mod logger;
 pub mod altimeter;
+pub mod arming;
+pub mod autopilot;
 pub mod ascii_hud;
 pub mod cli;
 pub mod configuration;
 pub mod event;
+pub mod failsafe;
+pub mod flight_mode;
+pub mod geofence;
 pub mod imu;
+pub mod ins;
 pub mod mixer;
 pub mod monitor;
 pub mod navigation;
 pub mod panic;
 pub mod schedule;
-pub mod speedometer;
+pub mod stabilizer;
 pub mod telemetry;
 
 pub use imu::IMU;
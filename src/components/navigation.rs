@@ -1,81 +1,184 @@
 use alloc::boxed::Box;
 use alloc::rc::Rc;
 
-use crate::algorithm::ComplementaryFilter;
+use nalgebra::Vector2;
+
+use crate::algorithm::l1::L1;
 use crate::alloc;
 use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
 use crate::datastructures::coordinate::{Displacement, Position};
 use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
 use crate::datastructures::data_source::{AgingStaticData, DataWriter, StaticData};
-use crate::datastructures::measurement::displacement::DistanceVector;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::ins::Validity;
+use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
 use crate::datastructures::measurement::unit::Meter;
-use crate::datastructures::measurement::{Altitude, VelocityVector};
-use crate::datastructures::waypoint::{Steerpoint, Waypoint};
+use crate::datastructures::measurement::VelocityVector;
+use crate::datastructures::waypoint::{Guidance, Home, Steerpoint, Waypoint};
 
-const CURRENT: usize = 0;
 const HOME: usize = 0;
 const MAX_WAYPOINT: usize = 32;
 
-pub struct Navigation<A, S> {
-    altimeter: A,
+pub struct Navigation<P, S> {
+    position: P,
     speedometer: S,
 
     gnss: Option<Box<dyn AgingStaticData<Position>>>,
+    flight_mode: Option<Box<dyn StaticData<FlightMode>>>,
+    validity: Option<Box<dyn StaticData<Validity>>>,
 
-    velocity: VelocityVector<f32, Meter>,
-    filters: [ComplementaryFilter<f32>; 3],
-    displacement: (f32, f32, f32),
     waypoints: [Waypoint; MAX_WAYPOINT],
     displacements: [Displacement<Meter>; MAX_WAYPOINT],
     output: Rc<SingularData<(Position, Steerpoint)>>,
+    home: Home,
+    home_output: Rc<SingularData<Home>>,
+    mission: Rc<SingularData<u8>>,
+    l1: L1,
+    acceptance_radius: f32,
+    guidance: Rc<SingularData<Guidance>>,
+    loiter_radius: f32,
+    rth_origin: Option<Vector2<f32>>,
+    loitering: bool,
+    loiter_center: Option<Vector2<f32>>,
     next_waypoint: u8,
     max_waypoint: u8,
 }
 
-impl<A, S> Navigation<A, S> {
-    pub fn new(altimeter: A, speedometer: S) -> Self {
-        Self {
-            altimeter,
+impl<P, S> Navigation<P, S> {
+    pub fn new(position: P, speedometer: S) -> Self {
+        let loiter_radius = config::get().autopilot.rth.loiter_radius as f32;
+        let config = &config::get().navigation;
+        let max_bank = config.max_bank_angle as f32 / DEGREE_PER_DAG;
+        let l1 = L1::new(config.l1_period as f32, config.l1_damping.into(), max_bank);
+        let mut navigation = Self {
+            position,
             speedometer,
             gnss: None,
-            velocity: VelocityVector::default(),
-            filters: [ComplementaryFilter::new(0.5, 0.02); 3],
-            displacement: (0.0, 0.0, 0.0),
+            flight_mode: None,
+            validity: None,
             waypoints: [Waypoint::default(); MAX_WAYPOINT],
             displacements: [Displacement::default(); MAX_WAYPOINT],
             output: Rc::new(SingularData::default()),
+            home: Home::default(),
+            home_output: Rc::new(SingularData::default()),
+            mission: Rc::new(SingularData::default()),
+            l1,
+            acceptance_radius: config.acceptance_radius as f32,
+            guidance: Rc::new(SingularData::default()),
+            loiter_radius,
+            rth_origin: None,
+            loitering: false,
+            loiter_center: None,
             next_waypoint: HOME as u8,
             max_waypoint: 1,
+        };
+        for (i, &waypoint) in config.waypoints.iter().enumerate() {
+            navigation.update_waypoint(HOME + 1 + i, waypoint.into());
         }
+        navigation
     }
 
     pub fn reader(&self) -> SingularDataSource<(Position, Steerpoint)> {
         SingularDataSource::new(&self.output)
     }
 
+    pub fn home_reader(&self) -> SingularDataSource<Home> {
+        SingularDataSource::new(&self.home_output)
+    }
+
+    /// Number of waypoints loaded besides home
+    pub fn mission_reader(&self) -> SingularDataSource<u8> {
+        SingularDataSource::new(&self.mission)
+    }
+
+    /// Desired course and bank angle towards steerpoint
+    pub fn guidance_reader(&self) -> SingularDataSource<Guidance> {
+        SingularDataSource::new(&self.guidance)
+    }
+
     pub fn set_gnss(&mut self, gnss: Box<dyn AgingStaticData<Position>>) {
         self.gnss = Some(gnss)
     }
 
+    /// Home altitude is captured once INS vertical position is valid,
+    /// or once altitude is non-zero without INS validity
+    pub fn set_validity(&mut self, validity: Box<dyn StaticData<Validity>>) {
+        self.validity = Some(validity)
+    }
+
+    /// Steers home instead of mission steerpoint while returning home,
+    /// circles where loiter started while loitering
+    pub fn set_flight_mode(&mut self, flight_mode: Box<dyn StaticData<FlightMode>>) {
+        self.flight_mode = Some(flight_mode)
+    }
+
     pub fn next_waypoint(&mut self) {
         self.next_waypoint = (self.next_waypoint + 1) % self.max_waypoint;
     }
 
+    /// Waypoints are appended in order after home
     pub fn update_waypoint(&mut self, index: usize, waypoint: Waypoint) {
-        if index >= MAX_WAYPOINT || index != self.max_waypoint as usize + 1 {
+        if index >= MAX_WAYPOINT || index != self.max_waypoint as usize {
             return;
         }
         self.waypoints[index] = waypoint;
         self.max_waypoint += 1;
+        self.mission.write(self.max_waypoint - 1);
         if index != HOME {
             self.displacements[index] = waypoint.position - self.waypoints[HOME].position;
         }
     }
+
+    // East and north in meter relative to home
+    fn relative(&self, position: Position) -> Vector2<f32> {
+        let displacement = position - self.waypoints[HOME].position;
+        Vector2::new(displacement.x.value() as f32, displacement.y.value() as f32)
+    }
+
+    // Tracks from where returning started to home, then loiters around home
+    fn return_to_home(&mut self, current: Vector2<f32>, velocity: Vector2<f32>) -> (f32, f32) {
+        let home = Vector2::new(0.0, 0.0);
+        let origin = *self.rth_origin.get_or_insert(current);
+        self.loitering |= current.norm() <= self.loiter_radius;
+        match self.loitering {
+            true => self.l1.loiter(home, self.loiter_radius, current, velocity),
+            false => self.l1.guide(origin, home, current, velocity),
+        }
+    }
+
+    fn guide(&mut self, position: Position, velocity: Vector2<f32>, mode: FlightMode) -> Guidance {
+        let current = self.relative(position);
+        if mode != FlightMode::ReturnToHome {
+            self.rth_origin = None;
+            self.loitering = false;
+        }
+        if mode != FlightMode::Loiter {
+            self.loiter_center = None;
+        }
+        let (course, bank) = if mode == FlightMode::ReturnToHome {
+            self.return_to_home(current, velocity)
+        } else if mode == FlightMode::Loiter {
+            let center = *self.loiter_center.get_or_insert(current);
+            self.l1.loiter(center, self.loiter_radius, current, velocity)
+        } else {
+            let to = self.relative(self.waypoints[self.next_waypoint as usize].position);
+            if self.max_waypoint > 1 && (to - current).norm() < self.acceptance_radius {
+                self.next_waypoint();
+            }
+            let to = self.relative(self.waypoints[self.next_waypoint as usize].position);
+            let previous = (self.next_waypoint + self.max_waypoint - 1) % self.max_waypoint;
+            let from = self.relative(self.waypoints[previous as usize].position);
+            self.l1.guide(from, to, current, velocity)
+        };
+        let course = (course * DEGREE_PER_DAG) as i16;
+        Guidance { course: if course >= 0 { course } else { 360 + course } as u16, bank }
+    }
 }
 
-impl<A, S> Schedulable for Navigation<A, S>
+impl<P, S> Schedulable for Navigation<P, S>
 where
-    A: StaticData<Altitude>,
+    P: StaticData<Position>,
     S: StaticData<VelocityVector<f32, Meter>>,
 {
     fn rate(&self) -> Rate {
@@ -84,43 +187,136 @@ where
 
     fn schedule(&mut self) -> bool {
         let rate = self.rate();
-        let dt = 1.0 / rate as f32;
 
-        if self.waypoints[HOME].position.latitude.0 == 0 {
+        if !self.home.fixed {
             if let Some(position) = self.gnss.as_mut().map(|gnss| gnss.read(rate)).flatten() {
-                self.waypoints[HOME].position = position;
-                self.displacement.0 = 0.0;
-                self.displacement.1 = 0.0;
+                self.waypoints[HOME].position.latitude = position.latitude;
+                self.waypoints[HOME].position.longitude = position.longitude;
+                self.home.fixed = true;
             }
         }
 
-        if self.waypoints[HOME].position.altitude.is_zero() {
-            self.waypoints[HOME].position.altitude = self.altimeter.read();
+        let position = self.position.read();
+        let vertical = match self.validity.as_mut() {
+            Some(validity) => validity.read().vertical,
+            None => !position.altitude.is_zero(),
+        };
+        if !self.home.altitude_set && vertical {
+            self.waypoints[HOME].position.altitude = position.altitude;
+            self.home.altitude_set = true;
         }
-        let height = self.altimeter.read() - self.waypoints[HOME].position.altitude;
-        let height = height.convert(|v| v as f32).to_unit(Meter);
-
-        let gnss = self.gnss.as_mut().map(|gnss| gnss.read(rate)).flatten().map(|position| {
-            (self.waypoints[HOME].position - position).convert(|v| v as f32).to_unit(Meter)
-        });
-
         let velocity = self.speedometer.read();
-        if let Some(position) = gnss {
-            self.displacement.0 = self.filters[0].filter(position.x.value(), velocity.x.value());
-            self.displacement.1 = self.filters[1].filter(position.y.value(), velocity.y.value());
-        } else {
-            self.displacement.0 += (velocity.x + (velocity.x - self.velocity.x) / 2.0).value() * dt;
-            self.displacement.1 += (velocity.y + (velocity.y - self.velocity.y) / 2.0).value() * dt;
-        }
-        self.displacement.2 = self.filters[2].filter(height.value(), velocity.z.value());
-        self.velocity = velocity;
-
-        let s: DistanceVector<f32, Meter> = self.displacement.into();
-        self.displacements[CURRENT] = s.convert(|v| v as i32);
-        let waypoint = self.waypoints[self.next_waypoint as usize];
-        let steerpoint = Steerpoint { index: self.next_waypoint, waypoint };
-        let position = self.waypoints[HOME].position - self.displacements[CURRENT];
+        let mode = self.flight_mode.as_mut().map(|m| m.read()).unwrap_or_default();
+        let rth = mode == FlightMode::ReturnToHome;
+        let velocity = Vector2::new(velocity.x.value(), velocity.y.value());
+        let guidance = self.guide(position, velocity, mode);
+        self.guidance.write(guidance);
+        let index = if rth { HOME as u8 } else { self.next_waypoint };
+        let steerpoint = Steerpoint { index, waypoint: self.waypoints[index as usize] };
         self.output.write((position, steerpoint));
+        self.home.position = self.waypoints[HOME].position;
+        self.home_output.write(self.home);
         true
     }
 }
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_home_altitude() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use crate::components::schedule::Schedulable;
+        use crate::config::{self, Config};
+        use crate::datastructures::coordinate::Position;
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::{DataWriter, NoDataSource, StaticData};
+        use crate::datastructures::ins::Validity;
+        use crate::datastructures::measurement::distance::Distance;
+        use crate::datastructures::measurement::unit::{CentiMeter, Meter};
+
+        use super::Navigation;
+
+        config::replace(Config::default());
+        let position = Rc::new(SingularData::default());
+        let validity = Rc::new(SingularData::default());
+        let mut navigation =
+            Navigation::new(SingularDataSource::new(&position), NoDataSource::new());
+        navigation.set_validity(Box::new(SingularDataSource::new(&validity)));
+        let mut home = navigation.home_reader();
+
+        // INS not ready yet
+        position.write(Position::default());
+        navigation.schedule();
+        assert!(!home.read().altitude_set);
+
+        let altitude = Distance::new(100, Meter).to_unit(CentiMeter);
+        position.write(Position { altitude, ..Default::default() });
+        validity.write(Validity { vertical: true, ..Default::default() });
+        navigation.schedule();
+        let altitude = Distance::new(150, Meter).to_unit(CentiMeter);
+        position.write(Position { altitude, ..Default::default() });
+        navigation.schedule();
+        let home = home.read();
+        assert!(home.altitude_set);
+        assert_eq!(home.position.altitude, Distance::new(100, Meter).to_unit(CentiMeter));
+    }
+
+    #[test]
+    #[serial]
+    fn test_mission() {
+        use alloc::boxed::Box;
+        use alloc::rc::Rc;
+
+        use crate::components::schedule::Schedulable;
+        use crate::config::geofence::Point;
+        use crate::config::navigation::Waypoint;
+        use crate::config::{self, Config};
+        use crate::datastructures::coordinate::{Latitude, Longitude, Position};
+        use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+        use crate::datastructures::data_source::{DataWriter, NoDataSource, StaticData};
+        use crate::datastructures::measurement::displacement::DistanceVector;
+        use crate::datastructures::measurement::unit::Meter;
+
+        use super::Navigation;
+
+        let latitude = Latitude::from_str("N22°32.000").unwrap();
+        let longitude = Longitude::from_str("E113°56.000").unwrap();
+        let home = Position { latitude, longitude, ..Default::default() };
+        let at = |x, y| home + DistanceVector::new(x, y, 100, Meter);
+        let to_waypoint = |position: Position| {
+            let point = Point { latitude: position.latitude, longitude: position.longitude };
+            Waypoint { point, altitude: 100 }
+        };
+
+        // North 1km then east 1km
+        let mut config = Config::default();
+        config.navigation.waypoints.push(to_waypoint(at(0, 1000))).ok();
+        config.navigation.waypoints.push(to_waypoint(at(1000, 1000))).ok();
+        config::replace(config);
+
+        let position = Rc::new(SingularData::default());
+        let gnss = Rc::new(SingularData::default());
+        let mut navigation =
+            Navigation::new(SingularDataSource::new(&position), NoDataSource::new());
+        navigation.set_gnss(Box::new(SingularDataSource::new(&gnss)));
+        let mut mission = navigation.mission_reader();
+        let mut reader = navigation.reader();
+        let mut guidance = navigation.guidance_reader();
+        assert_eq!(mission.read(), 2);
+
+        gnss.write(home);
+        let mut steerpoints = vec![];
+        for &(x, y) in [(0, 0), (0, 500), (0, 990), (500, 1000), (990, 1000)].iter() {
+            position.write(at(x, y));
+            navigation.schedule();
+            let (_, steerpoint) = reader.read();
+            steerpoints.push(steerpoint.index);
+            if (x, y) == (0, 500) {
+                assert!(guidance.read().course < 10 || guidance.read().course > 350);
+            }
+        }
+        assert_eq!(&steerpoints[..], &[1, 1, 2, 2, 0][..]);
+    }
+}
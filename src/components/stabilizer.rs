@@ -0,0 +1,312 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::algorithm::pid::PID;
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::config::stabilizer::PID as PIDConfig;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::{DataWriter, OptionData, StaticData};
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::input::ControlInput;
+use crate::datastructures::measurement::euler::{Euler, DEGREE_PER_DAG};
+
+const ROLL: usize = 0;
+const PITCH: usize = 1;
+const YAW: usize = 2;
+
+fn to_pid(config: &PIDConfig, output_limit: f32, anti_windup: bool) -> PID {
+    let mut pid = PID::new(config.kp.into(), config.ki.into(), config.kd.into());
+    pid.set_integral_limit(config.i_limit.into());
+    pid.set_output_limit(output_limit);
+    pid.set_anti_windup(anti_windup);
+    pid
+}
+
+fn to_i16(value: f32) -> i16 {
+    (value * i16::MAX as f32) as i16
+}
+
+fn to_ratio(value: i16) -> f32 {
+    value as f32 / i16::MAX as f32
+}
+
+/// Follows stick input as setpoint, angle loop generates desired rate for rate loop,
+/// output follows euler convention, positive roll output increases roll angle
+///
+/// Stick sets roll and pitch angle within max angle, acro mode sets body rates
+/// within max rate instead, yaw stick always sets yaw rate.
+/// Manual mode disables stabilizer, except for multirotor which falls back to acro
+pub struct Stabilizer<Q, G> {
+    attitude: Q,
+    gyroscope: G,
+    flight_mode: Option<Box<dyn StaticData<FlightMode>>>,
+    control_input: Option<Box<dyn StaticData<ControlInput>>>,
+    sample_rate: u16,
+    interval: f32,
+    max_rate: f32,
+    max_angle: f32,
+    multirotor: bool,
+    angle: [PID; 2],
+    rate: [PID; 3],
+    quaternion: Option<UnitQuaternion<f32>>,
+    gyro: Vector3<f32>,
+    output: Rc<SingularData<ControlInput>>,
+}
+
+impl<Q, G> Stabilizer<Q, G> {
+    pub fn new(attitude: Q, gyroscope: G, sample_rate: u16) -> Self {
+        let config = &config::get().stabilizer;
+        let max_rate = config.max_rate as f32 / DEGREE_PER_DAG;
+        let anti_windup = config.anti_windup;
+        Self {
+            attitude,
+            gyroscope,
+            flight_mode: None,
+            control_input: None,
+            sample_rate,
+            interval: 1.0 / sample_rate as f32,
+            max_rate,
+            max_angle: config.max_angle as f32 / DEGREE_PER_DAG,
+            multirotor: config::get().aircraft.configuration.is_multirotor(),
+            angle: [
+                to_pid(&config.roll.angle, max_rate, anti_windup),
+                to_pid(&config.pitch.angle, max_rate, anti_windup),
+            ],
+            rate: [
+                to_pid(&config.roll.rate, 1.0, anti_windup),
+                to_pid(&config.pitch.rate, 1.0, anti_windup),
+                to_pid(&config.yaw_rate, 1.0, anti_windup),
+            ],
+            quaternion: None,
+            gyro: Vector3::new(0.0, 0.0, 0.0),
+            output: Rc::new(SingularData::default()),
+        }
+    }
+
+    pub fn set_flight_mode(&mut self, flight_mode: Box<dyn StaticData<FlightMode>>) {
+        self.flight_mode = Some(flight_mode);
+    }
+
+    /// Stick input after failsafe and autopilot, used as setpoint
+    pub fn set_control_input(&mut self, control_input: Box<dyn StaticData<ControlInput>>) {
+        self.control_input = Some(control_input);
+    }
+
+    pub fn reader(&self) -> SingularDataSource<ControlInput> {
+        SingularDataSource::new(&self.output)
+    }
+
+    pub fn reset(&mut self) {
+        self.angle.iter_mut().for_each(|pid| pid.reset());
+        self.rate.iter_mut().for_each(|pid| pid.reset());
+    }
+
+    pub fn update(
+        &mut self,
+        mode: FlightMode,
+        input: &ControlInput,
+        quaternion: &UnitQuaternion<f32>,
+        gyro: &Vector3<f32>,
+    ) -> ControlInput {
+        let dt = self.interval;
+        let (roll, pitch) = (to_ratio(input.roll), to_ratio(input.pitch));
+        let mode = match mode {
+            FlightMode::Manual if self.multirotor => FlightMode::Acro,
+            _ => mode,
+        };
+        let (roll_rate, pitch_rate) = match mode {
+            FlightMode::Manual => {
+                self.reset();
+                return ControlInput::default();
+            }
+            FlightMode::Acro => (roll * self.max_rate, pitch * self.max_rate),
+            _ => {
+                let euler = Euler::from(*quaternion);
+                let roll_rate = self.angle[ROLL].update(roll * self.max_angle - euler.roll, dt);
+                let pitch_rate = self.angle[PITCH].update(pitch * self.max_angle - euler.pitch, dt);
+                (roll_rate, pitch_rate)
+            }
+        };
+        let yaw_rate = to_ratio(input.yaw) * self.max_rate;
+        let roll = self.rate[ROLL].update(roll_rate - gyro[1], dt);
+        let pitch = self.rate[PITCH].update(pitch_rate - gyro[0], dt);
+        let yaw = self.rate[YAW].update(yaw_rate - gyro[2], dt);
+        ControlInput {
+            roll: to_i16(roll),
+            pitch: to_i16(pitch),
+            yaw: to_i16(yaw),
+            ..Default::default()
+        }
+    }
+}
+
+impl<Q, G> Schedulable for Stabilizer<Q, G>
+where
+    Q: OptionData<UnitQuaternion<f32>>,
+    G: OptionData<Vector3<f32>>,
+{
+    fn rate(&self) -> Rate {
+        self.sample_rate as Rate
+    }
+
+    fn schedule(&mut self) -> bool {
+        while let Some(gyro) = self.gyroscope.read() {
+            self.gyro = gyro;
+        }
+        while let Some(quaternion) = self.attitude.read() {
+            self.quaternion = Some(quaternion);
+        }
+        let mode = self.flight_mode.as_mut().map(|m| m.read()).unwrap_or_default();
+        let input = self.control_input.as_mut().map(|i| i.read()).unwrap_or_default();
+        if let Some(quaternion) = self.quaternion {
+            let gyro = self.gyro;
+            let output = self.update(mode, &input, &quaternion, &gyro);
+            self.output.write(output);
+        }
+        true
+    }
+}
+
+mod test {
+    #[cfg(test)]
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    #[cfg(test)]
+    use crate::datastructures::input::ControlInput;
+
+    #[cfg(test)]
+    fn stabilizer() -> super::Stabilizer<(), ()> {
+        use crate::config::{self, Config};
+
+        config::replace(Config::default());
+        super::Stabilizer::new((), (), 50)
+    }
+
+    #[test]
+    #[serial]
+    fn test_stabilizer_attitude_error() {
+        use crate::datastructures::flight_mode::FlightMode::Stabilized;
+
+        let mut stabilizer = stabilizer();
+        let center = ControlInput::default();
+        let zero = Vector3::new(0.0, 0.0, 0.0);
+
+        let quaternion = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.5);
+        let output = stabilizer.update(Stabilized, &center, &quaternion, &zero);
+        assert!(output.roll < 0);
+        assert_eq!(output.yaw, 0);
+
+        stabilizer.reset();
+        let quaternion = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -0.5);
+        let output = stabilizer.update(Stabilized, &center, &quaternion, &zero);
+        assert!(output.pitch > 0);
+        assert_eq!(output.roll, 0);
+
+        stabilizer.reset();
+        let output = stabilizer.update(
+            Stabilized,
+            &center,
+            &UnitQuaternion::identity(),
+            &Vector3::new(0.0, 0.0, 1.0),
+        );
+        assert!(output.yaw < 0);
+        assert_eq!(output.pitch, 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_stabilizer_level_off() {
+        use crate::datastructures::flight_mode::FlightMode::Stabilized;
+        use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
+
+        let mut stabilizer = stabilizer();
+        let center = ControlInput::default();
+        let (mut roll, mut roll_rate) = (30.0 / DEGREE_PER_DAG, 0.0);
+        let dt = 1.0 / 50.0;
+        for _ in 0..5 * 50 {
+            let quaternion = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), roll);
+            let output = stabilizer.update(
+                Stabilized,
+                &center,
+                &quaternion,
+                &Vector3::new(0.0, roll_rate, 0.0),
+            );
+            let input = output.roll as f32 / i16::MAX as f32;
+            roll_rate += (20.0 * input - 2.0 * roll_rate) * dt;
+            roll += roll_rate * dt;
+        }
+        assert!((roll * DEGREE_PER_DAG).abs() < 1.0);
+        assert!((roll_rate * DEGREE_PER_DAG).abs() < 1.0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_stabilizer_flight_modes() {
+        use crate::datastructures::flight_mode::FlightMode;
+
+        let mut stabilizer = stabilizer();
+        let center = ControlInput::default();
+        let quaternion = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.5);
+        let zero = Vector3::new(0.0, 0.0, 0.0);
+        assert_eq!(stabilizer.update(FlightMode::Acro, &center, &quaternion, &zero).roll, 0);
+        let output = stabilizer.update(FlightMode::Acro, &center, &quaternion, &Vector3::y());
+        assert!(output.roll < 0);
+        let output = stabilizer.update(FlightMode::Manual, &center, &quaternion, &Vector3::y());
+        assert_eq!((output.roll, output.pitch, output.yaw), (0, 0, 0));
+    }
+
+    #[test]
+    #[serial]
+    fn test_stabilizer_stick_setpoint() {
+        use crate::datastructures::flight_mode::FlightMode::{Acro, Stabilized};
+        use crate::datastructures::measurement::euler::DEGREE_PER_DAG;
+
+        // Held half right roll stick and half yaw stick
+        let input = ControlInput { roll: i16::MAX / 2, yaw: i16::MAX / 2, ..Default::default() };
+        let dt = 1.0 / 50.0;
+        for &mode in [Acro, Stabilized].iter() {
+            let mut stabilizer = stabilizer();
+            let (mut roll, mut roll_rate, mut yaw_rate) = (0.0, 0.0, 0.0);
+            for _ in 0..30 * 50 {
+                let quaternion = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), roll);
+                let gyro = Vector3::new(0.0, roll_rate, yaw_rate);
+                let output = stabilizer.update(mode, &input, &quaternion, &gyro);
+                roll_rate += (20.0 * output.roll as f32 / i16::MAX as f32 - 2.0 * roll_rate) * dt;
+                yaw_rate += (20.0 * output.yaw as f32 / i16::MAX as f32 - 2.0 * yaw_rate) * dt;
+                if mode == Stabilized {
+                    roll += roll_rate * dt;
+                }
+            }
+            // Yaw rate follows stick within 180 degree per second
+            assert!((yaw_rate * DEGREE_PER_DAG - 90.0).abs() < 2.0);
+            match mode {
+                Acro => assert!((roll_rate * DEGREE_PER_DAG - 90.0).abs() < 2.0),
+                _ => {
+                    // Roll angle follows stick within 30 degree
+                    assert!((roll * DEGREE_PER_DAG - 15.0).abs() < 1.0);
+                    assert!((roll_rate * DEGREE_PER_DAG).abs() < 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_multirotor_manual() {
+        use crate::config::aircraft::Configuration;
+        use crate::config::{self, Config};
+        use crate::datastructures::flight_mode::FlightMode::Manual;
+
+        let mut config = Config::default();
+        config.aircraft.configuration = Configuration::QuadX;
+        config::replace(config);
+        let mut stabilizer = super::Stabilizer::<(), ()>::new((), (), 50);
+        let input = ControlInput { roll: i16::MAX / 2, ..Default::default() };
+        let zero = Vector3::new(0.0, 0.0, 0.0);
+        let output = stabilizer.update(Manual, &input, &UnitQuaternion::identity(), &zero);
+        assert!(output.roll > 0);
+    }
+}
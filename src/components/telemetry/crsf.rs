@@ -0,0 +1,157 @@
+#[allow(unused_imports)] // false warning
+use micromath::F32Ext;
+
+use crate::components::schedule::{Rate, Schedulable};
+use crate::config;
+use crate::datastructures::data_source::StaticData;
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::drivers::crsf::{self, encode_frame, MAX_FRAME_SIZE};
+use crate::hal::io::Write;
+
+use super::TelemetryData;
+
+const RADIAN_X10000_PER_DECI_DEGREE: f32 = core::f32::consts::PI / 1800.0 * 10000.0;
+
+fn put_u16(buffer: &mut [u8], value: u16) {
+    buffer[..2].copy_from_slice(&value.to_be_bytes());
+}
+
+fn put_i32(buffer: &mut [u8], value: i32) {
+    buffer[..4].copy_from_slice(&value.to_be_bytes());
+}
+
+fn to_radian_x10000(deci_degree: i16) -> u16 {
+    (deci_degree as f32 * RADIAN_X10000_PER_DECI_DEGREE) as i16 as u16
+}
+
+fn battery(data: &TelemetryData, payload: &mut [u8]) -> usize {
+    let millivolt = data.status.battery.0; // per cell
+    let cells = core::cmp::max(data.status.battery_cells, 1) as u32;
+    put_u16(&mut payload[0..], (millivolt as u32 * cells / 100) as u16);
+    put_u16(&mut payload[2..], 0); // current
+    payload[4..7].copy_from_slice(&[0, 0, 0]); // capacity used
+    let config = &config::get().battery;
+    let (min, max): (f32, f32) = (config.min_cell_voltage.into(), config.max_cell_voltage.into());
+    let remain = (millivolt as f32 / 1000.0 - min) / (max - min) * 100.0;
+    payload[7] = remain.max(0.0).min(100.0) as u8;
+    8
+}
+
+fn gps(data: &TelemetryData, payload: &mut [u8]) -> usize {
+    let position = &data.navigation.position;
+    // milli seconds of arc to degree * 10^7
+    put_i32(&mut payload[0..], (position.latitude.0 as i64 * 25 / 9) as i32);
+    put_i32(&mut payload[4..], (position.longitude.0 as i64 * 25 / 9) as i32);
+    let (x, y) = (data.navigation.speed_vector.x.value(), data.navigation.speed_vector.y.value());
+    put_u16(&mut payload[8..], ((x * x + y * y).sqrt() * 36.0) as u16); // km/h * 10
+    put_u16(&mut payload[10..], data.status.heading * 100);
+    let altitude = position.altitude.value() / 100 + 1000;
+    put_u16(&mut payload[12..], if altitude > 0 { altitude as u16 } else { 0 });
+    payload[14] = 0; // satellites not available
+    15
+}
+
+fn attitude(data: &TelemetryData, payload: &mut [u8]) -> usize {
+    let attitude = &data.status.attitude;
+    let heading = data.status.heading as i16;
+    let yaw = if heading > 180 { heading - 360 } else { heading };
+    put_u16(&mut payload[0..], to_radian_x10000(attitude.pitch));
+    put_u16(&mut payload[2..], to_radian_x10000(attitude.roll));
+    put_u16(&mut payload[4..], to_radian_x10000(yaw * 10));
+    6
+}
+
+fn vario(data: &TelemetryData, payload: &mut [u8]) -> usize {
+    let vertical_speed = data.navigation.speed_vector.z.value() * 100.0; // cm/s
+    put_u16(&mut payload[0..], vertical_speed as i16 as u16);
+    2
+}
+
+fn flight_mode(data: &TelemetryData, payload: &mut [u8]) -> usize {
+    let status = &data.status;
+    let name = match status.failsafe {
+        FailsafeStage::Normal => status.flight_mode.short_name(),
+        stage => stage.short_name(),
+    };
+    let bytes = name.as_bytes();
+    payload[..bytes.len()].copy_from_slice(bytes);
+    let mut size = bytes.len();
+    if !status.arming.armed {
+        payload[size] = b'*';
+        size += 1;
+    }
+    payload[size] = 0;
+    size + 1
+}
+
+const FRAMES: [(u8, fn(&TelemetryData, &mut [u8]) -> usize); 5] = [
+    (crsf::FRAME_TYPE_BATTERY_SENSOR, battery),
+    (crsf::FRAME_TYPE_GPS, gps),
+    (crsf::FRAME_TYPE_ATTITUDE, attitude),
+    (crsf::FRAME_TYPE_VARIO, vario),
+    (crsf::FRAME_TYPE_FLIGHT_MODE, flight_mode),
+];
+
+/// Sends one telemetry frame each schedule in round robin
+pub struct CrsfTelemetry<T, W> {
+    telemetry: T,
+    writer: W,
+    index: usize,
+}
+
+impl<T, W> CrsfTelemetry<T, W> {
+    pub fn new(telemetry: T, writer: W) -> Self {
+        Self { telemetry, writer, index: 0 }
+    }
+}
+
+impl<T: StaticData<TelemetryData>, W: Write> Schedulable for CrsfTelemetry<T, W> {
+    fn rate(&self) -> Rate {
+        50
+    }
+
+    fn schedule(&mut self) -> bool {
+        let data = self.telemetry.read();
+        let (frame_type, encode) = FRAMES[self.index];
+        self.index = (self.index + 1) % FRAMES.len();
+        let mut payload = [0u8; MAX_FRAME_SIZE - 4];
+        let size = encode(&data, &mut payload);
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        let size = encode_frame(frame_type, &payload[..size], &mut buffer);
+        self.writer.write(&buffer[..size]).ok();
+        true
+    }
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_crsf_telemetry_payload() {
+        use crate::components::telemetry::TelemetryData;
+        use crate::config::{self, Config};
+        use crate::datastructures::flight_mode::FlightMode;
+        use crate::datastructures::measurement::battery::Battery;
+
+        use super::{attitude, battery, flight_mode};
+
+        config::replace(Config::default());
+        let mut data = TelemetryData::default();
+        data.status.battery = Battery(3750);
+        data.status.battery_cells = 3;
+        data.status.attitude.roll = 900;
+        data.status.attitude.pitch = -450;
+        data.status.heading = 270;
+        data.status.flight_mode = FlightMode::Acro;
+
+        let mut payload = [0u8; 16];
+        assert_eq!(battery(&data, &mut payload), 8);
+        assert_eq!(&payload[..8], &[0, 112, 0, 0, 0, 0, 0, 50]);
+        assert_eq!(attitude(&data, &mut payload), 6);
+        let pitch = i16::from_be_bytes([payload[0], payload[1]]);
+        let roll = i16::from_be_bytes([payload[2], payload[3]]);
+        let yaw = i16::from_be_bytes([payload[4], payload[5]]);
+        assert_eq!((pitch, roll, yaw), (-7853, 15707, -15707));
+        assert_eq!(flight_mode(&data, &mut payload), 6);
+        assert_eq!(&payload[..6], b"ACRO*\0");
+    }
+}
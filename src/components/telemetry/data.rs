@@ -5,7 +5,7 @@ use crate::datastructures::input::ControlInput;
 use crate::datastructures::measurement::displacement::DistanceVector;
 use crate::datastructures::measurement::unit::Meter;
 use crate::datastructures::measurement::VelocityVector;
-use crate::datastructures::waypoint::Steerpoint;
+use crate::datastructures::waypoint::{Guidance, Steerpoint};
 
 use super::sensor::Sensor;
 use super::status::Status;
@@ -44,6 +44,7 @@ pub struct Navigation {
     pub position: Position,
     pub speed_vector: VelocityVector<f32, Meter>,
     pub steerpoint: Steerpoint,
+    pub guidance: Guidance,
 }
 
 impl core::fmt::Display for Navigation {
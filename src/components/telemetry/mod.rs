@@ -1,3 +1,4 @@
+pub mod crsf;
 pub mod data;
 pub mod sensor;
 pub mod status;
@@ -16,17 +17,22 @@ use nalgebra::UnitQuaternion;
 
 use crate::components::schedule::{Rate, Schedulable};
 use crate::config;
+use crate::datastructures::arming::Arming;
 use crate::datastructures::coordinate::{Position, SphericalCoordinate};
 use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
 use crate::datastructures::data_source::{AgingStaticData, DataWriter, StaticData};
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::geofence::Breach;
 use crate::datastructures::input::{ControlInput, RSSI};
+use crate::datastructures::ins::Validity;
 use crate::datastructures::measurement::battery::Battery;
 use crate::datastructures::measurement::euler::{Euler, DEGREE_PER_DAG};
 use crate::datastructures::measurement::unit::{FTpM, Knot, Meter};
 use crate::datastructures::measurement::{
     Acceleration, Altitude, Course, Gyro, Magnetism, VelocityVector,
 };
-use crate::datastructures::waypoint::Steerpoint;
+use crate::datastructures::waypoint::{Guidance, Steerpoint};
 use crate::datastructures::GNSSFixed;
 
 impl<U: Copy + Default + Into<u32>> Into<hud::SphericalCoordinate> for SphericalCoordinate<U> {
@@ -53,6 +59,12 @@ pub struct TelemetryUnit<A, B, C, G, IMU, S, NAV> {
     control_input: Option<Box<dyn AgingStaticData<ControlInput>>>,
     magnetometer: Option<Box<dyn StaticData<Magnetism>>>,
     gnss: Option<GNSS>,
+    flight_mode: Option<Box<dyn StaticData<FlightMode>>>,
+    arming: Option<Box<dyn StaticData<Arming>>>,
+    failsafe: Option<Box<dyn StaticData<FailsafeStage>>>,
+    guidance: Option<Box<dyn StaticData<Guidance>>>,
+    geofence: Option<Box<dyn StaticData<Breach>>>,
+    ins: Option<Box<dyn StaticData<Validity>>>,
 
     initial_altitude: Altitude,
     battery_cells: u8,
@@ -78,7 +90,7 @@ where
         }
         let battery = self.battery.read();
         if self.battery_cells == 0 {
-            self.battery_cells = core::cmp::min(battery.0 / 4200 + 1, 8) as u8;
+            self.battery_cells = battery.cells().unwrap_or(0);
         }
 
         let quaternion = self.imu.read();
@@ -114,9 +126,16 @@ where
             airspeed: vector.to_unit(Knot).distance().value() as u16,
             vario: vector.z.to_unit(FTpM).value() as i16,
             rssi: self.rssi.as_mut().map(|r| r.read(rate)).flatten().unwrap_or_default(),
-            battery: battery / self.battery_cells as u16,
+            battery: battery / core::cmp::max(self.battery_cells, 1) as u16,
+            battery_cells: self.battery_cells,
+            flight_mode: self.flight_mode.as_mut().map(|m| m.read()).unwrap_or_default(),
+            arming: self.arming.as_mut().map(|a| a.read()).unwrap_or_default(),
+            failsafe: self.failsafe.as_mut().map(|f| f.read()).unwrap_or_default(),
+            geofence: self.geofence.as_mut().map(|g| g.read()).unwrap_or_default(),
+            ins: self.ins.as_mut().map(|v| v.read()).unwrap_or_default(),
         };
-        let navigation = Navigation { position, speed_vector, steerpoint };
+        let guidance = self.guidance.as_mut().map(|g| g.read()).unwrap_or_default();
+        let navigation = Navigation { position, speed_vector, steerpoint, guidance };
         let sensor = Sensor { acceleration, gyro, magnetism, gnss };
         let misc = Misc { displacement, input: input_option.unwrap_or_default(), quaternion };
 
@@ -157,6 +176,12 @@ impl<A, B, C, G, IMU, S, NAV> TelemetryUnit<A, B, C, G, IMU, S, NAV> {
             control_input: None,
             magnetometer: None,
             gnss: None,
+            flight_mode: None,
+            arming: None,
+            failsafe: None,
+            guidance: None,
+            geofence: None,
+            ins: None,
 
             initial_altitude: Default::default(),
             battery_cells: config.battery.cells,
@@ -180,6 +205,30 @@ impl<A, B, C, G, IMU, S, NAV> TelemetryUnit<A, B, C, G, IMU, S, NAV> {
         self.gnss = Some(GNSS { fix, course })
     }
 
+    pub fn set_flight_mode(&mut self, flight_mode: Box<dyn StaticData<FlightMode>>) {
+        self.flight_mode = Some(flight_mode)
+    }
+
+    pub fn set_arming(&mut self, arming: Box<dyn StaticData<Arming>>) {
+        self.arming = Some(arming)
+    }
+
+    pub fn set_failsafe(&mut self, failsafe: Box<dyn StaticData<FailsafeStage>>) {
+        self.failsafe = Some(failsafe)
+    }
+
+    pub fn set_guidance(&mut self, guidance: Box<dyn StaticData<Guidance>>) {
+        self.guidance = Some(guidance)
+    }
+
+    pub fn set_geofence(&mut self, geofence: Box<dyn StaticData<Breach>>) {
+        self.geofence = Some(geofence)
+    }
+
+    pub fn set_ins_validity(&mut self, validity: Box<dyn StaticData<Validity>>) {
+        self.ins = Some(validity)
+    }
+
     pub fn reader(&self) -> SingularDataSource<TelemetryData> {
         SingularDataSource::new(&self.telemetry)
     }
@@ -1,6 +1,11 @@
 use ascii_osd_hud::telemetry as hud;
 
+use crate::datastructures::arming::Arming;
+use crate::datastructures::failsafe::FailsafeStage;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::geofence::Breach;
 use crate::datastructures::input::RSSI;
+use crate::datastructures::ins::Validity;
 use crate::datastructures::measurement::battery::Battery;
 use crate::datastructures::measurement::euler::Euler;
 use crate::datastructures::measurement::Altitude;
@@ -46,5 +51,11 @@ pub struct Status {
     pub airspeed: u16,
     pub vario: i16,
     pub rssi: RSSI,
-    pub battery: Battery,
+    pub battery: Battery, // per cell
+    pub battery_cells: u8,
+    pub flight_mode: FlightMode,
+    pub arming: Arming,
+    pub failsafe: FailsafeStage,
+    pub geofence: Breach,
+    pub ins: Validity,
 }
@@ -10,6 +10,19 @@ pub enum Configuration {
     Airplane,
     FlyingWing,
     VTail,
+    QuadX,
+    QuadPlus,
+    Hexa,
+    Tricopter,
+}
+
+impl Configuration {
+    pub fn is_multirotor(self) -> bool {
+        match self {
+            Self::QuadX | Self::QuadPlus | Self::Hexa | Self::Tricopter => true,
+            _ => false,
+        }
+    }
 }
 
 impl FromStr for Configuration {
@@ -20,6 +33,10 @@ impl FromStr for Configuration {
             "airplane" => Ok(Self::Airplane),
             "flying-wing" => Ok(Self::FlyingWing),
             "v-tail" => Ok(Self::VTail),
+            "quad-x" => Ok(Self::QuadX),
+            "quad-plus" => Ok(Self::QuadPlus),
+            "hexa" => Ok(Self::Hexa),
+            "tricopter" => Ok(Self::Tricopter),
             _ => Err(()),
         }
     }
@@ -31,6 +48,10 @@ impl Into<&str> for Configuration {
             Self::Airplane => "airplane",
             Self::FlyingWing => "flying-wing",
             Self::VTail => "v-tail",
+            Self::QuadX => "quad-x",
+            Self::QuadPlus => "quad-plus",
+            Self::Hexa => "hexa",
+            Self::Tricopter => "tricopter",
         }
     }
 }
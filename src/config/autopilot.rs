@@ -0,0 +1,341 @@
+use core::fmt::Write;
+use core::str::{FromStr, Split};
+
+use crate::datastructures::decimal::IntegerDecimal;
+use crate::datastructures::input::InputType;
+
+use super::setter::{Error, Setter, Value};
+use super::stabilizer::PID;
+use super::yaml::ToYAML;
+
+const DEFAULT_MAX_CLIMB_RATE: u8 = 5;
+const DEFAULT_HOVER_THROTTLE: u8 = 50;
+const DEFAULT_ATTITUDE_GAIN: IntegerDecimal = integer_decimal!(2_0, 1);
+const DEFAULT_MULTIROTOR_TILT: u8 = 11;
+const DEFAULT_ALTITUDE_PID: PID = PID {
+    kp: integer_decimal!(0_5, 1),
+    ki: integer_decimal!(0, 0),
+    kd: integer_decimal!(0, 0),
+    i_limit: integer_decimal!(0, 0),
+};
+const DEFAULT_CLIMB_RATE_PID: PID = PID {
+    kp: integer_decimal!(0_2, 1),
+    ki: integer_decimal!(0_05, 2),
+    kd: integer_decimal!(0, 0),
+    i_limit: integer_decimal!(0_3, 1),
+};
+const DEFAULT_HEADING_PID: PID = PID {
+    kp: integer_decimal!(1_0, 1),
+    ki: integer_decimal!(0, 0),
+    kd: integer_decimal!(0, 0),
+    i_limit: integer_decimal!(0, 0),
+};
+
+const DEFAULT_TIME_CONSTANT: u8 = 5;
+const DEFAULT_THROTTLE_TIME_CONSTANT: u8 = 2;
+const DEFAULT_INTEGRATOR_GAIN: IntegerDecimal = integer_decimal!(0_1, 1);
+const DEFAULT_CRUISE_SPEED: u8 = 15;
+const DEFAULT_TRIM_THROTTLE: u8 = 50;
+const DEFAULT_MAX_SINK_RATE: u8 = 5;
+const DEFAULT_MAX_PITCH: u8 = 20;
+
+/// Total energy control of fixed-wing, coordinates throttle and pitch
+#[derive(Copy, Clone, Debug)]
+pub struct TECS {
+    pub time_constant: u8,          // second
+    pub throttle_time_constant: u8, // second
+    pub integrator_gain: IntegerDecimal,
+    pub cruise_speed: u8,  // meter per second
+    pub trim_throttle: u8, // percent
+    pub max_sink_rate: u8, // meter per second
+    pub max_pitch: u8,     // degree
+}
+
+impl Default for TECS {
+    fn default() -> Self {
+        Self {
+            time_constant: DEFAULT_TIME_CONSTANT,
+            throttle_time_constant: DEFAULT_THROTTLE_TIME_CONSTANT,
+            integrator_gain: DEFAULT_INTEGRATOR_GAIN,
+            cruise_speed: DEFAULT_CRUISE_SPEED,
+            trim_throttle: DEFAULT_TRIM_THROTTLE,
+            max_sink_rate: DEFAULT_MAX_SINK_RATE,
+            max_pitch: DEFAULT_MAX_PITCH,
+        }
+    }
+}
+
+impl Setter for TECS {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "time-constant" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_TIME_CONSTANT);
+                self.time_constant = core::cmp::max(value, 1)
+            }
+            "throttle-time-constant" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_THROTTLE_TIME_CONSTANT);
+                self.throttle_time_constant = core::cmp::max(value, 1)
+            }
+            "integrator-gain" => {
+                self.integrator_gain = value.parse()?.unwrap_or(DEFAULT_INTEGRATOR_GAIN)
+            }
+            "cruise-speed" => self.cruise_speed = value.parse()?.unwrap_or(DEFAULT_CRUISE_SPEED),
+            "trim-throttle" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_TRIM_THROTTLE);
+                self.trim_throttle = core::cmp::min(value, 100)
+            }
+            "max-sink-rate" => self.max_sink_rate = value.parse()?.unwrap_or(DEFAULT_MAX_SINK_RATE),
+            "max-pitch" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_MAX_PITCH);
+                self.max_pitch = core::cmp::min(value, 45)
+            }
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for TECS {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "time-constant: {}", self.time_constant)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "throttle-time-constant: {}", self.throttle_time_constant)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "integrator-gain: {}", self.integrator_gain)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "cruise-speed: {}", self.cruise_speed)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "trim-throttle: {}", self.trim_throttle)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-sink-rate: {}", self.max_sink_rate)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-pitch: {}", self.max_pitch)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RthAction {
+    Loiter,
+    Land,
+}
+
+impl FromStr for RthAction {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "loiter" => Ok(Self::Loiter),
+            "land" => Ok(Self::Land),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for RthAction {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Loiter => "loiter",
+            Self::Land => "land",
+        }
+    }
+}
+
+const DEFAULT_MIN_ALTITUDE: u16 = 50;
+const DEFAULT_LOITER_RADIUS: u16 = 50;
+const DEFAULT_LANDING_SPEED: u8 = 1;
+
+/// Climbs to minimum altitude above home, returns home then loiters or lands
+#[derive(Copy, Clone, Debug)]
+pub struct RTH {
+    pub switch: Option<InputType>, // aux input triggering return-to-home
+    pub min_altitude: u16,         // meter above home
+    pub loiter_radius: u16,        // meter
+    pub action: RthAction,
+    pub landing_speed: u8, // meter per second
+}
+
+impl Default for RTH {
+    fn default() -> Self {
+        Self {
+            switch: None,
+            min_altitude: DEFAULT_MIN_ALTITUDE,
+            loiter_radius: DEFAULT_LOITER_RADIUS,
+            action: RthAction::Loiter,
+            landing_speed: DEFAULT_LANDING_SPEED,
+        }
+    }
+}
+
+impl Setter for RTH {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "switch" => {
+                self.switch = match value.parse()? {
+                    Some(InputType::Aux(index)) => Some(InputType::Aux(index)),
+                    Some(_) => return Err(Error::UnexpectedValue),
+                    None => None,
+                }
+            }
+            "min-altitude" => self.min_altitude = value.parse()?.unwrap_or(DEFAULT_MIN_ALTITUDE),
+            "loiter-radius" => {
+                let radius = value.parse()?.unwrap_or(DEFAULT_LOITER_RADIUS);
+                self.loiter_radius = core::cmp::max(radius, 10)
+            }
+            "action" => self.action = value.parse()?.unwrap_or(RthAction::Loiter),
+            "landing-speed" => self.landing_speed = value.parse()?.unwrap_or(DEFAULT_LANDING_SPEED),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for RTH {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        if let Some(switch) = self.switch {
+            self.write_indent(indent, w)?;
+            writeln!(w, "switch: {}", switch)?;
+        }
+        self.write_indent(indent, w)?;
+        writeln!(w, "min-altitude: {}", self.min_altitude)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "loiter-radius: {}", self.loiter_radius)?;
+        self.write_indent(indent, w)?;
+        let action: &str = self.action.into();
+        writeln!(w, "action: {}", action)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "landing-speed: {}", self.landing_speed)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Autopilot {
+    pub engage: Option<InputType>, // aux input engaging altitude and heading hold
+    pub max_climb_rate: u8,        // meter per second
+    pub hover_throttle: u8,        // percent, multirotor throttle holding altitude
+    pub attitude_gain: IntegerDecimal, // full deflection per radian of attitude error
+    pub multirotor_tilt: u8,       // degree, nose down pitch of multirotor flying home
+    pub altitude: PID,             // meter to climb rate
+    pub climb_rate: PID,           // meter per second to control output
+    pub heading: PID,              // radian to control output
+    pub tecs: TECS,
+    pub rth: RTH,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            engage: None,
+            max_climb_rate: DEFAULT_MAX_CLIMB_RATE,
+            hover_throttle: DEFAULT_HOVER_THROTTLE,
+            attitude_gain: DEFAULT_ATTITUDE_GAIN,
+            multirotor_tilt: DEFAULT_MULTIROTOR_TILT,
+            altitude: DEFAULT_ALTITUDE_PID,
+            climb_rate: DEFAULT_CLIMB_RATE_PID,
+            heading: DEFAULT_HEADING_PID,
+            tecs: TECS::default(),
+            rth: RTH::default(),
+        }
+    }
+}
+
+impl Setter for Autopilot {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "engage" => {
+                self.engage = match value.parse()? {
+                    Some(InputType::Aux(index)) => Some(InputType::Aux(index)),
+                    Some(_) => return Err(Error::UnexpectedValue),
+                    None => None,
+                }
+            }
+            "max-climb-rate" => {
+                self.max_climb_rate = value.parse()?.unwrap_or(DEFAULT_MAX_CLIMB_RATE)
+            }
+            "hover-throttle" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_HOVER_THROTTLE);
+                self.hover_throttle = core::cmp::min(value, 100)
+            }
+            "attitude-gain" => self.attitude_gain = value.parse()?.unwrap_or(DEFAULT_ATTITUDE_GAIN),
+            "multirotor-tilt" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_MULTIROTOR_TILT);
+                self.multirotor_tilt = core::cmp::min(value, 45)
+            }
+            "altitude" => return self.altitude.set(path, value),
+            "climb-rate" => return self.climb_rate.set(path, value),
+            "heading" => return self.heading.set(path, value),
+            "tecs" => return self.tecs.set(path, value),
+            "rth" => return self.rth.set(path, value),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Autopilot {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        if let Some(engage) = self.engage {
+            self.write_indent(indent, w)?;
+            writeln!(w, "engage: {}", engage)?;
+        }
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-climb-rate: {}", self.max_climb_rate)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "hover-throttle: {}", self.hover_throttle)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "attitude-gain: {}", self.attitude_gain)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "multirotor-tilt: {}", self.multirotor_tilt)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "altitude:")?;
+        self.altitude.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "climb-rate:")?;
+        self.climb_rate.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "heading:")?;
+        self.heading.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "tecs:")?;
+        self.tecs.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "rth:")?;
+        self.rth.write_to(indent + 1, w)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_autopilot_attitude() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::yaml::{ToYAML, YamlParser};
+
+        use super::Autopilot;
+
+        let autopilot = Autopilot::default();
+        assert_eq!(autopilot.attitude_gain, "2.0".parse().unwrap());
+        assert_eq!(autopilot.multirotor_tilt, 11);
+
+        let yaml = "\
+        max-climb-rate: 5\n\
+        hover-throttle: 40\n\
+        attitude-gain: 1.5\n\
+        multirotor-tilt: 15\n";
+        let autopilot: Autopilot = YamlParser::new(yaml).parse();
+        assert_eq!(autopilot.attitude_gain, "1.5".parse().unwrap());
+        assert_eq!(autopilot.multirotor_tilt, 15);
+
+        let mut buf = String::new();
+        autopilot.write_to(0, &mut buf).ok();
+        assert!(buf.starts_with(yaml));
+    }
+}
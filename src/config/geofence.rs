@@ -0,0 +1,292 @@
+use alloc::borrow::ToOwned;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::fmt::Write;
+use core::str::{FromStr, Split};
+
+use heapless::consts::{U4, U8};
+use heapless::Vec;
+
+use crate::datastructures::coordinate::{Latitude, Longitude, Position};
+use crate::datastructures::flight_mode::FlightMode;
+
+use super::setter::{split_index, Error, Setter, Value};
+use super::yaml::ToYAML;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BreachAction {
+    Warn,
+    ReturnToHome,
+    Loiter,
+}
+
+impl BreachAction {
+    /// Flight mode taken over on breach, none if only warning on OSD
+    pub fn flight_mode(self) -> Option<FlightMode> {
+        match self {
+            Self::Warn => None,
+            Self::ReturnToHome => Some(FlightMode::ReturnToHome),
+            Self::Loiter => Some(FlightMode::Loiter),
+        }
+    }
+}
+
+impl FromStr for BreachAction {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "warn" => Ok(Self::Warn),
+            "return-to-home" => Ok(Self::ReturnToHome),
+            "loiter" => Ok(Self::Loiter),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for BreachAction {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::ReturnToHome => "return-to-home",
+            Self::Loiter => "loiter",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ZoneType {
+    Inclusion,
+    Exclusion,
+}
+
+impl FromStr for ZoneType {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "inclusion" => Ok(Self::Inclusion),
+            "exclusion" => Ok(Self::Exclusion),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for ZoneType {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Inclusion => "inclusion",
+            Self::Exclusion => "exclusion",
+        }
+    }
+}
+
+/// Polygon vertex formatted like `N22°32.100 E113°56.200`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Point {
+    pub latitude: Latitude,
+    pub longitude: Longitude,
+}
+
+impl Into<Position> for Point {
+    fn into(self) -> Position {
+        Position { latitude: self.latitude, longitude: self.longitude, ..Default::default() }
+    }
+}
+
+impl FromStr for Point {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        let mut split = string.split(' ').filter(|s| s.len() > 0);
+        let latitude = split.next().map(|s| Latitude::from_str(s)).flatten().ok_or(())?;
+        let longitude = split.next().map(|s| Longitude::from_str(s)).flatten().ok_or(())?;
+        Ok(Self { latitude, longitude })
+    }
+}
+
+impl core::fmt::Display for Point {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} {}", self.latitude, self.longitude)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Zone {
+    pub zone_type: ZoneType,
+    pub points: Vec<Point, U8>,
+}
+
+impl Default for Zone {
+    fn default() -> Self {
+        Self { zone_type: ZoneType::Inclusion, points: Vec::new() }
+    }
+}
+
+impl Zone {
+    /// Zones with less than 3 points enclose nothing
+    pub fn is_polygon(&self) -> bool {
+        self.points.len() >= 3
+    }
+}
+
+impl Setter for Zone {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match split_index(path.next().ok_or(Error::MalformedPath)?)? {
+            ("type", None) => self.zone_type = value.parse()?.unwrap_or(ZoneType::Inclusion),
+            ("points", None) => self.points.clear(),
+            ("points", Some(index)) => {
+                let point = value.parse()?.ok_or(Error::ExpectValue)?;
+                if index < self.points.len() {
+                    self.points[index] = point;
+                } else if index == self.points.len() {
+                    self.points.push(point).map_err(|_| Error::MalformedPath)?;
+                } else {
+                    return Err(Error::MalformedPath);
+                }
+            }
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Zone {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        let zone_type: &str = self.zone_type.into();
+        writeln!(w, "- type: {}", zone_type)?;
+        self.write_indent(indent + 1, w)?;
+        writeln!(w, "points:")?;
+        for point in self.points.iter() {
+            self.write_indent(indent + 2, w)?;
+            writeln!(w, "- {}", point)?;
+        }
+        Ok(())
+    }
+}
+
+pub type Zones = Vec<Zone, U4>;
+
+/// Cylinder around home with radius of max distance, 0 disables either limit,
+/// polygon zones are loaded from config or file
+#[derive(Clone, Debug)]
+pub struct Geofence {
+    pub max_altitude: u16, // meter above home
+    pub max_distance: u16, // meter from home
+    pub action: BreachAction,
+    pub file: Rc<String>,
+    pub zones: Zones,
+}
+
+impl Default for Geofence {
+    fn default() -> Self {
+        Self {
+            max_altitude: 0,
+            max_distance: 0,
+            action: BreachAction::Warn,
+            file: Rc::new(String::default()),
+            zones: Zones::new(),
+        }
+    }
+}
+
+impl Setter for Geofence {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match split_index(path.next().ok_or(Error::MalformedPath)?)? {
+            ("max-altitude", None) => self.max_altitude = value.parse()?.unwrap_or(0),
+            ("max-distance", None) => self.max_distance = value.parse()?.unwrap_or(0),
+            ("action", None) => self.action = value.parse()?.unwrap_or(BreachAction::Warn),
+            ("file", None) => self.file = Rc::new(value.0.unwrap_or_default().to_owned()),
+            ("zones", None) => self.zones.clear(),
+            ("zones", Some(index)) => {
+                if index == self.zones.len() {
+                    if !self.zones.last().map(|zone| zone.is_polygon()).unwrap_or(true) {
+                        return Err(Error::MalformedPath);
+                    }
+                    self.zones.push(Zone::default()).map_err(|_| Error::MalformedPath)?;
+                } else if index > self.zones.len() {
+                    return Err(Error::MalformedPath);
+                }
+                return self.zones[index].set(path, value);
+            }
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Geofence {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-altitude: {}", self.max_altitude)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-distance: {}", self.max_distance)?;
+        self.write_indent(indent, w)?;
+        let action: &str = self.action.into();
+        writeln!(w, "action: {}", action)?;
+        if self.file.as_str() != "" {
+            self.write_indent(indent, w)?;
+            writeln!(w, "file: {}", self.file.as_str())?;
+        }
+        if self.zones.len() > 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "zones:")?;
+            for zone in self.zones.iter() {
+                zone.write_to(indent + 1, w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_geofence_zones() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::yaml::{ToYAML, YamlParser};
+
+        use super::{Geofence, ZoneType};
+
+        let yaml = "\
+        max-altitude: 120\n\
+        max-distance: 500\n\
+        action: loiter\n\
+        zones:\n  \
+          - type: inclusion\n    \
+            points:\n      \
+              - N22°32.100 E113°56.200\n      \
+              - N22°32.100 E113°56.400\n      \
+              - N22°32.300 E113°56.300\n  \
+          - type: exclusion\n    \
+            points:\n      \
+              - S01°00.000 W002°00.000\n";
+        let geofence: Geofence = YamlParser::new(yaml).parse();
+        assert_eq!(geofence.zones.len(), 2);
+        assert_eq!(geofence.zones[0].points.len(), 3);
+        assert_eq!(geofence.zones[1].zone_type, ZoneType::Exclusion);
+
+        let mut buf = String::new();
+        geofence.write_to(0, &mut buf).ok();
+        assert_eq!(buf, yaml);
+    }
+
+    #[test]
+    fn test_geofence_incomplete_zone() {
+        use crate::config::setter::{Setter, Value};
+
+        use super::Geofence;
+
+        let mut geofence = Geofence::default();
+        let point = "N22°32.100 E113°56.200";
+        assert!(geofence.set(&mut "zones[0].points[0]".split('.'), Value::of(point)).is_ok());
+        assert!(geofence.set(&mut "zones[0].points[1]".split('.'), Value::of(point)).is_ok());
+        assert!(geofence.set(&mut "zones[1].points[0]".split('.'), Value::of(point)).is_err());
+        assert!(geofence.set(&mut "zones[0].points[2]".split('.'), Value::of(point)).is_ok());
+        assert!(geofence.set(&mut "zones[1].points[0]".split('.'), Value::of(point)).is_ok());
+        assert_eq!(geofence.zones.len(), 2);
+    }
+}
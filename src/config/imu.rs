@@ -1,5 +1,5 @@
 use core::fmt::Write;
-use core::str::Split;
+use core::str::{FromStr, Split};
 
 use crate::datastructures::decimal::IntegerDecimal;
 use crate::datastructures::measurement::{Axes, Gain};
@@ -111,11 +111,170 @@ impl ToYAML for Mahony {
     }
 }
 
+const DEFAULT_BETA: IntegerDecimal = integer_decimal!(0_1, 1);
+
+#[derive(Debug, Copy, Clone)]
+pub struct Madgwick {
+    pub beta: IntegerDecimal,
+}
+
+impl Default for Madgwick {
+    fn default() -> Self {
+        Self { beta: DEFAULT_BETA }
+    }
+}
+
+impl Setter for Madgwick {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "beta" => self.beta = value.parse()?.unwrap_or(DEFAULT_BETA),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Madgwick {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "beta: {}", self.beta)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AHRS {
+    Mahony,
+    Madgwick,
+    EKF,
+}
+
+impl Default for AHRS {
+    fn default() -> Self {
+        Self::Mahony
+    }
+}
+
+impl FromStr for AHRS {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "mahony" => Ok(Self::Mahony),
+            "madgwick" => Ok(Self::Madgwick),
+            "ekf" => Ok(Self::EKF),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for AHRS {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Mahony => "mahony",
+            Self::Madgwick => "madgwick",
+            Self::EKF => "ekf",
+        }
+    }
+}
+
+const DEFAULT_GYRO_NOISE: IntegerDecimal = integer_decimal!(0_5, 1);
+const DEFAULT_GYRO_BIAS_NOISE: IntegerDecimal = integer_decimal!(0_01, 2);
+const DEFAULT_ACCEL_NOISE: IntegerDecimal = integer_decimal!(0_05, 2);
+const DEFAULT_HEADING_NOISE: u8 = 5;
+
+#[derive(Debug, Copy, Clone)]
+pub struct EKF {
+    pub gyro_noise: IntegerDecimal,      // degree per second
+    pub gyro_bias_noise: IntegerDecimal, // degree per second squared
+    pub accel_noise: IntegerDecimal,     // g
+    pub heading_noise: u8,               // degree
+}
+
+impl Default for EKF {
+    fn default() -> Self {
+        Self {
+            gyro_noise: DEFAULT_GYRO_NOISE,
+            gyro_bias_noise: DEFAULT_GYRO_BIAS_NOISE,
+            accel_noise: DEFAULT_ACCEL_NOISE,
+            heading_noise: DEFAULT_HEADING_NOISE,
+        }
+    }
+}
+
+impl Setter for EKF {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "gyro-noise" => self.gyro_noise = value.parse()?.unwrap_or(DEFAULT_GYRO_NOISE),
+            "gyro-bias-noise" => {
+                self.gyro_bias_noise = value.parse()?.unwrap_or(DEFAULT_GYRO_BIAS_NOISE)
+            }
+            "accel-noise" => self.accel_noise = value.parse()?.unwrap_or(DEFAULT_ACCEL_NOISE),
+            "heading-noise" => self.heading_noise = value.parse()?.unwrap_or(DEFAULT_HEADING_NOISE),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for EKF {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "gyro-noise: {}", self.gyro_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "gyro-bias-noise: {}", self.gyro_bias_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "accel-noise: {}", self.accel_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "heading-noise: {}", self.heading_noise)
+    }
+}
+
+const DEFAULT_MAX_ACCEL_DEVIATION: IntegerDecimal = integer_decimal!(0_3, 1);
+
+/// Accelerometer reference correction before feeding AHRS
+#[derive(Debug, Copy, Clone)]
+pub struct Compensation {
+    pub centripetal: bool,
+    pub max_accel_deviation: IntegerDecimal, // g, accelerometer ignored beyond, 0 to disable
+}
+
+impl Default for Compensation {
+    fn default() -> Self {
+        Self { centripetal: true, max_accel_deviation: DEFAULT_MAX_ACCEL_DEVIATION }
+    }
+}
+
+impl Setter for Compensation {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "centripetal" => self.centripetal = value.parse()?.unwrap_or(true),
+            "max-accel-deviation" => {
+                self.max_accel_deviation = value.parse()?.unwrap_or(DEFAULT_MAX_ACCEL_DEVIATION)
+            }
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Compensation {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "centripetal: {}", self.centripetal)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-accel-deviation: {}", self.max_accel_deviation)
+    }
+}
+
 #[derive(Default, Debug, Copy, Clone)]
 pub struct IMU {
     pub accelerometer: Accelerometer,
     pub magnetometer: Magnetometer,
+    pub ahrs: AHRS,
     pub mahony: Mahony,
+    pub madgwick: Madgwick,
+    pub ekf: EKF,
+    pub compensation: Compensation,
 }
 
 impl Setter for IMU {
@@ -123,7 +282,14 @@ impl Setter for IMU {
         match path.next().ok_or(Error::MalformedPath)? {
             "accelerometer" => self.accelerometer.set(path, value),
             "magnetometer" => self.magnetometer.set(path, value),
+            "ahrs" => {
+                self.ahrs = value.parse()?.unwrap_or_default();
+                Ok(())
+            }
             "mahony" => self.mahony.set(path, value),
+            "madgwick" => self.madgwick.set(path, value),
+            "ekf" => self.ekf.set(path, value),
+            "compensation" => self.compensation.set(path, value),
             _ => return Err(Error::MalformedPath),
         }
     }
@@ -139,8 +305,24 @@ impl ToYAML for IMU {
         writeln!(w, "magnetometer:")?;
         self.magnetometer.write_to(indent + 1, w)?;
 
+        self.write_indent(indent, w)?;
+        let ahrs: &str = self.ahrs.into();
+        writeln!(w, "ahrs: {}", ahrs)?;
+
         self.write_indent(indent, w)?;
         writeln!(w, "mahony:")?;
-        self.mahony.write_to(indent + 1, w)
+        self.mahony.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "madgwick:")?;
+        self.madgwick.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "ekf:")?;
+        self.ekf.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "compensation:")?;
+        self.compensation.write_to(indent + 1, w)
     }
 }
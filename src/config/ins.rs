@@ -0,0 +1,69 @@
+use core::fmt::Write;
+use core::str::Split;
+
+use crate::datastructures::decimal::IntegerDecimal;
+
+use super::setter::{Error, Setter, Value};
+use super::yaml::ToYAML;
+
+const DEFAULT_ACCEL_NOISE: IntegerDecimal = integer_decimal!(0_5, 1);
+const DEFAULT_ACCEL_BIAS_NOISE: IntegerDecimal = integer_decimal!(0_01, 2);
+const DEFAULT_GNSS_POSITION_NOISE: u8 = 3;
+const DEFAULT_GNSS_VELOCITY_NOISE: IntegerDecimal = integer_decimal!(0_5, 1);
+const DEFAULT_BARO_NOISE: IntegerDecimal = integer_decimal!(1_0, 1);
+
+#[derive(Copy, Clone, Debug)]
+pub struct INS {
+    pub accel_noise: IntegerDecimal,         // meter per second squared
+    pub accel_bias_noise: IntegerDecimal,    // meter per second cubed
+    pub gnss_position_noise: u8,             // meter
+    pub gnss_velocity_noise: IntegerDecimal, // meter per second
+    pub baro_noise: IntegerDecimal,          // meter
+}
+
+impl Default for INS {
+    fn default() -> Self {
+        Self {
+            accel_noise: DEFAULT_ACCEL_NOISE,
+            accel_bias_noise: DEFAULT_ACCEL_BIAS_NOISE,
+            gnss_position_noise: DEFAULT_GNSS_POSITION_NOISE,
+            gnss_velocity_noise: DEFAULT_GNSS_VELOCITY_NOISE,
+            baro_noise: DEFAULT_BARO_NOISE,
+        }
+    }
+}
+
+impl Setter for INS {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "accel-noise" => self.accel_noise = value.parse()?.unwrap_or(DEFAULT_ACCEL_NOISE),
+            "accel-bias-noise" => {
+                self.accel_bias_noise = value.parse()?.unwrap_or(DEFAULT_ACCEL_BIAS_NOISE)
+            }
+            "gnss-position-noise" => {
+                self.gnss_position_noise = value.parse()?.unwrap_or(DEFAULT_GNSS_POSITION_NOISE)
+            }
+            "gnss-velocity-noise" => {
+                self.gnss_velocity_noise = value.parse()?.unwrap_or(DEFAULT_GNSS_VELOCITY_NOISE)
+            }
+            "baro-noise" => self.baro_noise = value.parse()?.unwrap_or(DEFAULT_BARO_NOISE),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for INS {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "accel-noise: {}", self.accel_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "accel-bias-noise: {}", self.accel_bias_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "gnss-position-noise: {}", self.gnss_position_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "gnss-velocity-noise: {}", self.gnss_velocity_noise)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "baro-noise: {}", self.baro_noise)
+    }
+}
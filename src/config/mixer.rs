@@ -0,0 +1,208 @@
+use core::fmt::Write;
+use core::str::Split;
+
+use heapless::consts::{U4, U8};
+use heapless::{LinearMap, Vec};
+
+use crate::datastructures::input::InputType;
+
+use super::aircraft::Configuration;
+use super::peripherals::pwm::{Identifier, ServoType, PWM};
+use super::setter::{split_index, Error, Setter, Value};
+use super::yaml::ToYAML;
+
+/// Weighted contribution of an input to an output, all values in percent
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub input: InputType,
+    pub weight: i8,
+    pub offset: i8,
+    pub min: i8,
+    pub max: i8,
+}
+
+impl Rule {
+    pub fn new(input: InputType, weight: i8) -> Self {
+        Self { input, weight, offset: 0, min: -100, max: 100 }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::new(InputType::Throttle, 100)
+    }
+}
+
+fn limit_percent(value: i8) -> i8 {
+    value.max(-100).min(100)
+}
+
+impl Setter for Rule {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "input" => {
+                self.input = match value.parse()?.ok_or(Error::ExpectValue)? {
+                    InputType::Mode | InputType::Arm => return Err(Error::UnexpectedValue),
+                    input => input,
+                }
+            }
+            "weight" => self.weight = limit_percent(value.parse()?.unwrap_or(100)),
+            "offset" => self.offset = limit_percent(value.parse()?.unwrap_or(0)),
+            "min" => self.min = limit_percent(value.parse()?.unwrap_or(-100)),
+            "max" => self.max = limit_percent(value.parse()?.unwrap_or(100)),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Rule {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "- input: {}", self.input)?;
+        self.write_indent(indent + 1, w)?;
+        writeln!(w, "weight: {}", self.weight)?;
+        if self.offset != 0 {
+            self.write_indent(indent + 1, w)?;
+            writeln!(w, "offset: {}", self.offset)?;
+        }
+        if self.min != -100 {
+            self.write_indent(indent + 1, w)?;
+            writeln!(w, "min: {}", self.min)?;
+        }
+        if self.max != 100 {
+            self.write_indent(indent + 1, w)?;
+            writeln!(w, "max: {}", self.max)?;
+        }
+        Ok(())
+    }
+}
+
+pub type Rules = Vec<Rule, U4>;
+
+fn rules(rules: &[Rule]) -> Rules {
+    rules.iter().cloned().collect()
+}
+
+/// Built-in rules for outputs not listed in mixer table
+pub fn preset(configuration: Configuration, pwm: &PWM) -> Rules {
+    let servo_type = match pwm {
+        PWM::Motor(_) => return rules(&[Rule::new(InputType::Throttle, 100)]),
+        PWM::Servo(servo) => servo.servo_type,
+    };
+    match (configuration, servo_type) {
+        (_, ServoType::Aileron) => rules(&[Rule::new(InputType::Roll, 100)]),
+        (_, ServoType::Elevator) => rules(&[Rule::new(InputType::Pitch, 100)]),
+        (_, ServoType::Rudder) => rules(&[Rule::new(InputType::Yaw, 100)]),
+        (Configuration::FlyingWing, ServoType::ElevonLeft) => {
+            rules(&[Rule::new(InputType::Roll, -100), Rule::new(InputType::Pitch, 100)])
+        }
+        (Configuration::FlyingWing, ServoType::ElevonRight) => {
+            rules(&[Rule::new(InputType::Roll, 100), Rule::new(InputType::Pitch, 100)])
+        }
+        (Configuration::VTail, ServoType::ElevonLeft) => {
+            rules(&[Rule::new(InputType::Yaw, 100), Rule::new(InputType::Pitch, 100)])
+        }
+        (Configuration::VTail, ServoType::ElevonRight) => {
+            rules(&[Rule::new(InputType::Yaw, -100), Rule::new(InputType::Pitch, 100)])
+        }
+        _ => Rules::new(),
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Mixer(pub LinearMap<Identifier, Rules, U8>);
+
+impl Mixer {
+    pub fn any(&self) -> bool {
+        self.0.len() > 0
+    }
+
+    /// Rules from mixer table, otherwise preset of aircraft configuration
+    pub fn rules(&self, name: &str, configuration: Configuration, pwm: &PWM) -> Rules {
+        let id = name.parse().ok();
+        match id.map(|id| self.0.get(&id)).flatten() {
+            Some(rules) => rules.clone(),
+            None => preset(configuration, pwm),
+        }
+    }
+}
+
+impl Setter for Mixer {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        let (id_string, index) = split_index(path.next().ok_or(Error::MalformedPath)?)?;
+        let id: Identifier = id_string.parse().map_err(|_| Error::MalformedPath)?;
+        let index = match index {
+            Some(index) => index,
+            None => {
+                self.0.remove(&id);
+                return Ok(());
+            }
+        };
+        if !self.0.contains_key(&id) {
+            self.0.insert(id, Rules::new()).map_err(|_| Error::MalformedPath)?;
+        }
+        let rules = &mut self.0[&id];
+        if index == rules.len() {
+            rules.push(Rule::default()).map_err(|_| Error::MalformedPath)?;
+        } else if index > rules.len() {
+            return Err(Error::MalformedPath);
+        }
+        rules[index].set(path, value)
+    }
+}
+
+impl ToYAML for Mixer {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        for (id, rules) in self.0.iter() {
+            self.write_indent(indent, w)?;
+            writeln!(w, "{}:", id)?;
+            for rule in rules.iter() {
+                rule.write_to(indent + 1, w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_mixer_table() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::aircraft::Configuration;
+        use crate::config::peripherals::pwm::{Servo, ServoType, PWM};
+        use crate::config::yaml::{ToYAML, YamlParser};
+        use crate::datastructures::input::InputType;
+
+        use super::{Mixer, Rule};
+
+        let yaml = "\
+        PWM2:\n  \
+          - input: roll\n    \
+            weight: 100\n    \
+            max: 60\n  \
+          - input: flaps\n    \
+            weight: -50\n    \
+            offset: 10\n";
+        let mixer: Mixer = YamlParser::new(yaml).parse();
+        let elevon = PWM::Servo(Servo::of(ServoType::ElevonLeft));
+        let rules = mixer.rules("PWM2", Configuration::FlyingWing, &elevon);
+        let mut expected = Rule::new(InputType::Roll, 100);
+        expected.max = 60;
+        assert_eq!(rules[0], expected);
+        let mut expected = Rule::new(InputType::Flaps, -50);
+        expected.offset = 10;
+        assert_eq!(rules[1], expected);
+
+        let mut buf = String::new();
+        mixer.write_to(0, &mut buf).ok();
+        assert_eq!(buf, yaml);
+
+        let rules = mixer.rules("PWM3", Configuration::FlyingWing, &elevon);
+        assert_eq!(rules[0], Rule::new(InputType::Roll, -100));
+        assert_eq!(rules[1], Rule::new(InputType::Pitch, 100));
+    }
+}
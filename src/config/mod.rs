@@ -1,30 +1,41 @@
 pub mod aircraft;
+pub mod autopilot;
 pub mod battery;
 pub mod board;
+pub mod geofence;
 pub mod imu;
+pub mod ins;
+pub mod mixer;
+pub mod navigation;
 pub mod osd;
 pub mod peripherals;
 pub mod receiver;
 pub mod setter;
+pub mod stabilizer;
 pub mod yaml;
 
 use core::fmt::Write;
 use core::str::Split;
 
-use crate::datastructures::decimal::IntegerDecimal;
 use crate::datastructures::measurement::{Axes, Gain};
 use crate::hal::io::Read;
 
 pub use aircraft::Aircraft;
+pub use autopilot::Autopilot;
 pub use battery::Battery;
 pub use board::Board;
+pub use geofence::Geofence;
 pub use imu::IMU;
+pub use ins::INS;
+pub use mixer::Mixer;
+pub use navigation::Navigation;
 pub use osd::{Offset, Standard, OSD};
 pub use peripherals::pwm::{PWMs, Protocol, PWM};
 pub use peripherals::serial::{Config as SerialConfig, Serials};
 pub use peripherals::Peripherals;
 pub use receiver::Receiver;
 use setter::{Error, Setter, Value};
+pub use stabilizer::Stabilizer;
 use yaml::{ToYAML, YamlParser};
 
 impl Setter for Axes {
@@ -73,46 +84,21 @@ impl ToYAML for Gain {
     }
 }
 
-const DEFAULT_KP: IntegerDecimal = integer_decimal!(0_25, 2);
-
-#[derive(Clone)]
-pub struct Speedometer {
-    pub kp: IntegerDecimal,
-}
-
-impl Default for Speedometer {
-    fn default() -> Self {
-        Self { kp: DEFAULT_KP }
-    }
-}
-
-impl Setter for Speedometer {
-    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
-        match path.next().ok_or(Error::MalformedPath)? {
-            "kp" => self.kp = value.parse()?.unwrap_or(DEFAULT_KP),
-            _ => return Err(Error::MalformedPath),
-        }
-        Ok(())
-    }
-}
-
-impl ToYAML for Speedometer {
-    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
-        self.write_indent(indent, w)?;
-        writeln!(w, "kp: {}", self.kp)
-    }
-}
-
 #[derive(Default, Clone)]
 pub struct Config {
     version: u8,
     pub aircraft: Aircraft,
+    pub autopilot: Autopilot,
     pub battery: Battery,
     pub board: Board,
+    pub geofence: Geofence,
     pub imu: IMU,
+    pub ins: INS,
+    pub mixer: Mixer,
+    pub navigation: Navigation,
     pub osd: OSD,
     pub receiver: Receiver,
-    pub speedometer: Speedometer,
+    pub stabilizer: Stabilizer,
     pub peripherals: Peripherals,
 }
 
@@ -127,12 +113,17 @@ impl Setter for Config {
         self.version += 1;
         match path.next().ok_or(Error::MalformedPath)? {
             "aircraft" => self.aircraft.set(path, value),
+            "autopilot" => self.autopilot.set(path, value),
             "battery" => self.battery.set(path, value),
             "board" => self.board.set(path, value),
+            "geofence" => self.geofence.set(path, value),
             "imu" => self.imu.set(path, value),
+            "ins" => self.ins.set(path, value),
+            "mixer" => self.mixer.set(path, value),
+            "navigation" => self.navigation.set(path, value),
             "osd" => self.osd.set(path, value),
             "receiver" => self.receiver.set(path, value),
-            "speedometer" => self.speedometer.set(path, value),
+            "stabilizer" => self.stabilizer.set(path, value),
             "peripherals" => self.peripherals.set(path, value),
             _ => Err(Error::MalformedPath),
         }
@@ -145,6 +136,10 @@ impl ToYAML for Config {
         writeln!(w, "aircraft:")?;
         self.aircraft.write_to(indent + 1, w)?;
 
+        self.write_indent(indent, w)?;
+        writeln!(w, "autopilot:")?;
+        self.autopilot.write_to(indent + 1, w)?;
+
         self.write_indent(indent, w)?;
         writeln!(w, "battery:")?;
         self.battery.write_to(indent + 1, w)?;
@@ -153,10 +148,28 @@ impl ToYAML for Config {
         writeln!(w, "board:")?;
         self.board.write_to(indent + 1, w)?;
 
+        self.write_indent(indent, w)?;
+        writeln!(w, "geofence:")?;
+        self.geofence.write_to(indent + 1, w)?;
+
         self.write_indent(indent, w)?;
         writeln!(w, "imu:")?;
         self.imu.write_to(indent + 1, w)?;
 
+        self.write_indent(indent, w)?;
+        writeln!(w, "ins:")?;
+        self.ins.write_to(indent + 1, w)?;
+
+        if self.mixer.any() {
+            self.write_indent(indent, w)?;
+            writeln!(w, "mixer:")?;
+            self.mixer.write_to(indent + 1, w)?;
+        }
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "navigation:")?;
+        self.navigation.write_to(indent + 1, w)?;
+
         self.write_indent(indent, w)?;
         writeln!(w, "osd:")?;
         self.osd.write_to(indent + 1, w)?;
@@ -166,8 +179,8 @@ impl ToYAML for Config {
         self.receiver.write_to(indent + 1, w)?;
 
         self.write_indent(indent, w)?;
-        writeln!(w, "speedometer:")?;
-        self.speedometer.write_to(indent + 1, w)?;
+        writeln!(w, "stabilizer:")?;
+        self.stabilizer.write_to(indent + 1, w)?;
 
         if self.peripherals.any() {
             writeln!(w, "peripherals:")?;
@@ -184,6 +197,7 @@ impl core::fmt::Display for Config {
 }
 
 static mut CONFIG: Option<Config> = None;
+static mut LOADED: bool = false;
 
 #[inline]
 pub fn get() -> &'static Config {
@@ -191,8 +205,9 @@ pub fn get() -> &'static Config {
 }
 
 pub fn load<E>(reader: &mut dyn Read<Error = E>) -> &'static Config {
-    let mut buffer = [0u8; 2048];
+    let mut buffer = [0u8; 4096];
     let size = reader.read(&mut buffer).ok().unwrap_or(0);
+    unsafe { LOADED = size > 0 };
     let config = if size > 0 {
         YamlParser::new(unsafe { core::str::from_utf8_unchecked(&buffer[..size]) }).parse()
     } else {
@@ -202,6 +217,10 @@ pub fn load<E>(reader: &mut dyn Read<Error = E>) -> &'static Config {
     get()
 }
 
+pub fn loaded() -> bool {
+    unsafe { LOADED }
+}
+
 pub fn replace(mut config: Config) {
     config.version += 1;
     unsafe { CONFIG = Some(config) }
@@ -0,0 +1,155 @@
+use core::fmt::Write;
+use core::str::{FromStr, Split};
+
+use heapless::consts::U16;
+use heapless::Vec;
+
+use crate::datastructures::coordinate::Position;
+use crate::datastructures::decimal::IntegerDecimal;
+use crate::datastructures::measurement::distance::Distance;
+use crate::datastructures::measurement::unit::{CentiMeter, Meter};
+use crate::datastructures::waypoint::Waypoint as NavigationWaypoint;
+
+use super::geofence::Point;
+use super::setter::{split_index, Error, Setter, Value};
+use super::yaml::ToYAML;
+
+const DEFAULT_ACCEPTANCE_RADIUS: u16 = 50;
+const DEFAULT_L1_PERIOD: u8 = 20;
+const DEFAULT_L1_DAMPING: IntegerDecimal = integer_decimal!(0_75, 2);
+const DEFAULT_MAX_BANK_ANGLE: u8 = 30;
+
+/// Mission waypoint formatted like `N22°32.100 E113°56.200 120`, altitude in meter
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Waypoint {
+    pub point: Point,
+    pub altitude: u16,
+}
+
+impl Into<NavigationWaypoint> for Waypoint {
+    fn into(self) -> NavigationWaypoint {
+        let mut position: Position = self.point.into();
+        position.altitude = Distance::new(self.altitude as i32, Meter).to_unit(CentiMeter);
+        NavigationWaypoint { name: "WPT", position }
+    }
+}
+
+impl FromStr for Waypoint {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        let point = Point::from_str(string)?;
+        let mut split = string.split(' ').filter(|s| s.len() > 0).skip(2);
+        let altitude = split.next().map(|s| s.parse().ok()).flatten().ok_or(())?;
+        Ok(Self { point, altitude })
+    }
+}
+
+impl core::fmt::Display for Waypoint {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} {}", self.point, self.altitude)
+    }
+}
+
+pub type Waypoints = Vec<Waypoint, U16>;
+
+#[derive(Clone, Debug)]
+pub struct Navigation {
+    pub acceptance_radius: u16, // meter
+    pub l1_period: u8,          // second
+    pub l1_damping: IntegerDecimal,
+    pub max_bank_angle: u8, // degree
+    pub waypoints: Waypoints,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self {
+            acceptance_radius: DEFAULT_ACCEPTANCE_RADIUS,
+            l1_period: DEFAULT_L1_PERIOD,
+            l1_damping: DEFAULT_L1_DAMPING,
+            max_bank_angle: DEFAULT_MAX_BANK_ANGLE,
+            waypoints: Waypoints::new(),
+        }
+    }
+}
+
+impl Setter for Navigation {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match split_index(path.next().ok_or(Error::MalformedPath)?)? {
+            ("acceptance-radius", None) => {
+                self.acceptance_radius = value.parse()?.unwrap_or(DEFAULT_ACCEPTANCE_RADIUS)
+            }
+            ("l1-period", None) => self.l1_period = value.parse()?.unwrap_or(DEFAULT_L1_PERIOD),
+            ("l1-damping", None) => self.l1_damping = value.parse()?.unwrap_or(DEFAULT_L1_DAMPING),
+            ("max-bank-angle", None) => {
+                let angle = value.parse()?.unwrap_or(DEFAULT_MAX_BANK_ANGLE);
+                self.max_bank_angle = core::cmp::min(angle, 80)
+            }
+            ("waypoints", None) => self.waypoints.clear(),
+            ("waypoints", Some(index)) => {
+                let waypoint = value.parse()?.ok_or(Error::ExpectValue)?;
+                if index < self.waypoints.len() {
+                    self.waypoints[index] = waypoint;
+                } else if index == self.waypoints.len() {
+                    self.waypoints.push(waypoint).map_err(|_| Error::MalformedPath)?;
+                } else {
+                    return Err(Error::MalformedPath);
+                }
+            }
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Navigation {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "acceptance-radius: {}", self.acceptance_radius)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "l1-period: {}", self.l1_period)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "l1-damping: {}", self.l1_damping)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-bank-angle: {}", self.max_bank_angle)?;
+        if self.waypoints.len() > 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "waypoints:")?;
+            for waypoint in self.waypoints.iter() {
+                self.write_indent(indent + 1, w)?;
+                writeln!(w, "- {}", waypoint)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_navigation_waypoints() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::yaml::{ToYAML, YamlParser};
+
+        use super::Navigation;
+
+        let yaml = "\
+        acceptance-radius: 50\n\
+        l1-period: 20\n\
+        l1-damping: 0.75\n\
+        max-bank-angle: 30\n\
+        waypoints:\n  \
+          - N22°32.100 E113°56.200 120\n  \
+          - N22°32.300 E113°56.300 80\n";
+        let navigation: Navigation = YamlParser::new(yaml).parse();
+        assert_eq!(navigation.waypoints.len(), 2);
+        assert_eq!(navigation.waypoints[1].altitude, 80);
+
+        let mut buf = String::new();
+        navigation.write_to(0, &mut buf).ok();
+        assert_eq!(buf, yaml);
+    }
+}
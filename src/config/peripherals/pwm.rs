@@ -31,6 +31,66 @@ impl core::fmt::Display for Identifier {
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Protocol {
     PWM,
+    OneShot125,
+    OneShot42,
+    Multishot,
+    DShot150,
+    DShot300,
+    DShot600,
+}
+
+impl Protocol {
+    /// Max update rate that still leaves room for longest pulse
+    pub fn max_rate(self) -> u16 {
+        match self {
+            Self::PWM => 490,
+            Self::OneShot125 => 2000,
+            Self::OneShot42 => 8000,
+            Self::Multishot => 32000,
+            _ => u16::MAX,
+        }
+    }
+
+    /// Bit rate in kbit/s for digital protocols
+    pub fn dshot_rate(self) -> Option<u16> {
+        match self {
+            Self::DShot150 => Some(150),
+            Self::DShot300 => Some(300),
+            Self::DShot600 => Some(600),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "PWM" => Ok(Self::PWM),
+            "OneShot125" => Ok(Self::OneShot125),
+            "OneShot42" => Ok(Self::OneShot42),
+            "Multishot" => Ok(Self::Multishot),
+            "DShot150" => Ok(Self::DShot150),
+            "DShot300" => Ok(Self::DShot300),
+            "DShot600" => Ok(Self::DShot600),
+            _ => Err(()),
+        }
+    }
+}
+
+impl core::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::PWM => write!(f, "PWM"),
+            Self::OneShot125 => write!(f, "OneShot125"),
+            Self::OneShot42 => write!(f, "OneShot42"),
+            Self::Multishot => write!(f, "Multishot"),
+            Self::DShot150 => write!(f, "DShot150"),
+            Self::DShot300 => write!(f, "DShot300"),
+            Self::DShot600 => write!(f, "DShot600"),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -38,20 +98,40 @@ pub struct Motor {
     pub protocol: Protocol,
     pub index: u8,
     pub rate: u16,
+    pub idle: u8, // percent of throttle when armed
+    // micro seconds of standard PWM, scaled accordingly for other protocols
+    pub min_command: u16,
+    pub min_throttle: u16,
+    pub max_throttle: u16,
 }
 
+const MIN_PULSE: u16 = 1000;
+const MAX_PULSE: u16 = 2000;
+
 impl Motor {
     pub fn new(protocol: Protocol, index: u8, rate: u16) -> Self {
-        Self { protocol, index, rate }
+        Self {
+            protocol,
+            index,
+            rate,
+            idle: 0,
+            min_command: MIN_PULSE,
+            min_throttle: MIN_PULSE,
+            max_throttle: MAX_PULSE,
+        }
     }
 }
 
 impl Default for Motor {
     fn default() -> Self {
-        Self { protocol: Protocol::PWM, index: 0, rate: 400 }
+        Self::new(Protocol::PWM, 0, 400)
     }
 }
 
+fn limit_pulse(pulse: u16) -> u16 {
+    cmp::max(cmp::min(pulse, MAX_PULSE), MIN_PULSE)
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ServoType {
     Aileron,
@@ -59,6 +139,7 @@ pub enum ServoType {
     Rudder,
     ElevonLeft,
     ElevonRight,
+    Custom,
 }
 
 impl Into<&str> for ServoType {
@@ -69,6 +150,7 @@ impl Into<&str> for ServoType {
             Self::Rudder => "rudder",
             Self::ElevonLeft => "elevon-left",
             Self::ElevonRight => "elevon-right",
+            Self::Custom => "servo",
         }
     }
 }
@@ -79,15 +161,36 @@ pub struct Servo {
     pub min_angle: i8,
     pub max_angle: i8,
     pub reversed: bool,
+    pub center: u16,      // micro seconds
+    pub subtrim: i16,     // micro seconds
+    pub min_endpoint: u8, // percentage of travel below center
+    pub max_endpoint: u8, // percentage of travel above center
+    pub slew_rate: u16,   // degrees per second, 0 means unlimited
+    pub rate: u16,
 }
 
+const DEFAULT_CENTER: u16 = 1500;
+const DEFAULT_SERVO_RATE: u16 = 50;
+const MAX_SERVO_RATE: u16 = 333; // leaves room for 2.5ms pulse
+
 impl Servo {
     pub fn new(servo_type: ServoType, min_angle: i8, max_angle: i8, reversed: bool) -> Self {
-        Self { servo_type, min_angle, max_angle, reversed }
+        Self {
+            servo_type,
+            min_angle,
+            max_angle,
+            reversed,
+            center: DEFAULT_CENTER,
+            subtrim: 0,
+            min_endpoint: 100,
+            max_endpoint: 100,
+            slew_rate: 0,
+            rate: DEFAULT_SERVO_RATE,
+        }
     }
 
     pub fn of(servo_type: ServoType) -> Self {
-        Self { servo_type, min_angle: -90, max_angle: 90, reversed: false }
+        Self::new(servo_type, -90, 90, false)
     }
 }
 
@@ -100,8 +203,15 @@ pub enum PWM {
 impl PWM {
     pub fn rate(self) -> u16 {
         match self {
-            Self::Motor(motor) => motor.rate,
-            _ => 50,
+            Self::Motor(motor) => cmp::min(motor.rate, motor.protocol.max_rate()),
+            Self::Servo(servo) => cmp::min(servo.rate, MAX_SERVO_RATE),
+        }
+    }
+
+    pub fn protocol(self) -> Protocol {
+        match self {
+            Self::Motor(motor) => motor.protocol,
+            _ => Protocol::PWM,
         }
     }
 }
@@ -118,6 +228,7 @@ impl Setter for PWM {
                 "rudder" => Self::Servo(Servo::of(ServoType::Rudder)),
                 "elevon-left" => Self::Servo(Servo::of(ServoType::ElevonLeft)),
                 "elevon-right" => Self::Servo(Servo::of(ServoType::ElevonRight)),
+                "servo" => Self::Servo(Servo::of(ServoType::Custom)),
                 _ => return Err(Error::UnexpectedValue),
             };
             return Ok(());
@@ -125,12 +236,18 @@ impl Setter for PWM {
         match self {
             Self::Motor(ref mut motor) => match key {
                 "index" => motor.index = value.parse()?.unwrap_or(0),
-                "protocol" => match value.0 {
-                    Some("PWM") => motor.protocol = Protocol::PWM,
-                    Some(_) => return Err(Error::UnexpectedValue),
-                    _ => motor.protocol = Protocol::PWM,
-                },
+                "protocol" => motor.protocol = value.parse()?.unwrap_or(Protocol::PWM),
                 "rate" => motor.rate = value.parse()?.unwrap_or(400),
+                "idle" => motor.idle = cmp::min(value.parse()?.unwrap_or(0), 50),
+                "min-command" => {
+                    motor.min_command = limit_pulse(value.parse()?.unwrap_or(MIN_PULSE))
+                }
+                "min-throttle" => {
+                    motor.min_throttle = limit_pulse(value.parse()?.unwrap_or(MIN_PULSE))
+                }
+                "max-throttle" => {
+                    motor.max_throttle = limit_pulse(value.parse()?.unwrap_or(MAX_PULSE))
+                }
                 _ => return Err(Error::MalformedPath),
             },
             Self::Servo(ref mut servo) => match key {
@@ -143,6 +260,17 @@ impl Setter for PWM {
                     servo.max_angle = cmp::max(cmp::min(max, 90), 0)
                 }
                 "reversed" => servo.reversed = value.parse()?.unwrap_or_default(),
+                "center" => {
+                    let center = value.parse()?.unwrap_or(DEFAULT_CENTER);
+                    servo.center = cmp::max(cmp::min(center, 2000), 1000)
+                }
+                "subtrim" => {
+                    servo.subtrim = cmp::max(cmp::min(value.parse()?.unwrap_or(0), 500), -500)
+                }
+                "min-endpoint" => servo.min_endpoint = cmp::min(value.parse()?.unwrap_or(100), 150),
+                "max-endpoint" => servo.max_endpoint = cmp::min(value.parse()?.unwrap_or(100), 150),
+                "slew-rate" => servo.slew_rate = value.parse()?.unwrap_or(0),
+                "rate" => servo.rate = value.parse()?.unwrap_or(DEFAULT_SERVO_RATE),
                 _ => return Err(Error::MalformedPath),
             },
         }
@@ -158,14 +286,26 @@ impl ToYAML for PWM {
                 writeln!(w, "type: motor")?;
                 self.write_indent(indent, w)?;
                 writeln!(w, "index: {}", motor.index)?;
-                match motor.protocol {
-                    Protocol::PWM => {
-                        self.write_indent(indent, w)?;
-                        writeln!(w, "protocol: PWM")?;
-                    }
-                }
+                self.write_indent(indent, w)?;
+                writeln!(w, "protocol: {}", motor.protocol)?;
                 self.write_indent(indent, w)?;
                 writeln!(w, "rate: {}", motor.rate)?;
+                if motor.idle > 0 {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "idle: {}", motor.idle)?;
+                }
+                if motor.min_command != MIN_PULSE {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "min-command: {}", motor.min_command)?;
+                }
+                if motor.min_throttle != MIN_PULSE {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "min-throttle: {}", motor.min_throttle)?;
+                }
+                if motor.max_throttle != MAX_PULSE {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "max-throttle: {}", motor.max_throttle)?;
+                }
             }
             Self::Servo(servo) => {
                 self.write_indent(indent, w)?;
@@ -183,6 +323,30 @@ impl ToYAML for PWM {
                     self.write_indent(indent, w)?;
                     writeln!(w, "reversed: true")?;
                 }
+                if servo.center != DEFAULT_CENTER {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "center: {}", servo.center)?;
+                }
+                if servo.subtrim != 0 {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "subtrim: {}", servo.subtrim)?;
+                }
+                if servo.min_endpoint != 100 {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "min-endpoint: {}", servo.min_endpoint)?;
+                }
+                if servo.max_endpoint != 100 {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "max-endpoint: {}", servo.max_endpoint)?;
+                }
+                if servo.slew_rate != 0 {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "slew-rate: {}", servo.slew_rate)?;
+                }
+                if servo.rate != DEFAULT_SERVO_RATE {
+                    self.write_indent(indent, w)?;
+                    writeln!(w, "rate: {}", servo.rate)?;
+                }
             }
         }
         Ok(())
@@ -106,10 +106,24 @@ impl SbusConfig {
     }
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub struct CrsfConfig {
+    pub baudrate: u32,
+}
+
+impl Default for CrsfConfig {
+    fn default() -> Self {
+        Self { baudrate: 420_000 }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum Config {
     GNSS(GNSSConfig),
     SBUS(SbusConfig),
+    CRSF(CrsfConfig),
+    CRSFTelemetry(CrsfConfig),
+    IBUS,
 }
 
 impl Setter for Config {
@@ -119,6 +133,9 @@ impl Setter for Config {
             *self = match value.0 {
                 Some("GNSS") => Self::GNSS(GNSSConfig::default()),
                 Some("SBUS") => Self::SBUS(SbusConfig::default()),
+                Some("CRSF") => Self::CRSF(CrsfConfig::default()),
+                Some("CRSF-telemetry") => Self::CRSFTelemetry(CrsfConfig::default()),
+                Some("IBUS") => Self::IBUS,
                 Some(_) => return Err(Error::UnexpectedValue),
                 _ => return Err(Error::ExpectValue),
             };
@@ -136,6 +153,11 @@ impl Setter for Config {
                 "half-duplex" => sbus.half_duplex = value.parse()?.unwrap_or(false),
                 _ => return Err(Error::MalformedPath),
             },
+            Self::CRSF(ref mut crsf) | Self::CRSFTelemetry(ref mut crsf) => match key {
+                "baudrate" => crsf.baudrate = value.parse()?.unwrap_or(420_000),
+                _ => return Err(Error::MalformedPath),
+            },
+            Self::IBUS => return Err(Error::MalformedPath),
         }
         Ok(())
     }
@@ -161,6 +183,17 @@ impl ToYAML for Config {
                 self.write_indent(indent, w)?;
                 writeln!(w, "half-duplex: {}", sbus.half_duplex)
             }
+            Self::CRSF(crsf) => {
+                writeln!(w, "type: CRSF")?;
+                self.write_indent(indent, w)?;
+                writeln!(w, "baudrate: {}", crsf.baudrate)
+            }
+            Self::CRSFTelemetry(crsf) => {
+                writeln!(w, "type: CRSF-telemetry")?;
+                self.write_indent(indent, w)?;
+                writeln!(w, "baudrate: {}", crsf.baudrate)
+            }
+            Self::IBUS => writeln!(w, "type: IBUS"),
         }
     }
 }
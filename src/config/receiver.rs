@@ -1,18 +1,31 @@
+use core::cmp;
 use core::fmt::Write;
-use core::str::Split;
+use core::str::{FromStr, Split};
 
-use heapless::consts::U18;
-use heapless::LinearMap;
+use heapless::consts::{U20, U6};
+use heapless::{LinearMap, Vec};
 
+use crate::datastructures::flight_mode::FlightMode;
 use crate::datastructures::input::InputType;
 
-use super::setter::{Error, Setter, Value};
+use super::setter::{split_index, Error, Setter, Value};
 use super::yaml::ToYAML;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Input {
     pub channel: u8,
     pub scale: u8,
+    pub deadband: u8, // percentage of half range around center
+    pub expo: u8,     // percentage of cubic curve
+    pub rate: u8,     // percentage applied after expo
+    pub reversed: bool,
+    pub subtrim: i8, // percentage of half range
+}
+
+impl Input {
+    pub fn new(channel: u8) -> Self {
+        Self { channel, scale: 100, deadband: 0, expo: 0, rate: 100, reversed: false, subtrim: 0 }
+    }
 }
 
 impl Setter for Input {
@@ -23,14 +36,51 @@ impl Setter for Input {
                 self.channel = self.channel.wrapping_sub(1)
             }
             "scale" => self.scale = value.parse()?.unwrap_or(100),
+            "deadband" => self.deadband = cmp::min(value.parse()?.unwrap_or(0), 50),
+            "expo" => self.expo = cmp::min(value.parse()?.unwrap_or(0), 100),
+            "rate" => self.rate = value.parse()?.unwrap_or(100),
+            "reversed" => self.reversed = value.parse()?.unwrap_or_default(),
+            "subtrim" => self.subtrim = cmp::max(cmp::min(value.parse()?.unwrap_or(0), 100), -100),
             _ => return Err(Error::MalformedPath),
         }
         Ok(())
     }
 }
 
+impl ToYAML for Input {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "channel: {}", self.channel + 1)?;
+        if self.scale != 100 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "scale: {}", self.scale)?;
+        }
+        if self.deadband != 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "deadband: {}", self.deadband)?;
+        }
+        if self.expo != 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "expo: {}", self.expo)?;
+        }
+        if self.rate != 100 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "rate: {}", self.rate)?;
+        }
+        if self.reversed {
+            self.write_indent(indent, w)?;
+            writeln!(w, "reversed: true")?;
+        }
+        if self.subtrim != 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "subtrim: {}", self.subtrim)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Default)]
-pub struct Inputs(pub LinearMap<InputType, Input, U18>);
+pub struct Inputs(pub LinearMap<InputType, Input, U20>);
 
 impl Setter for Inputs {
     fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
@@ -39,7 +89,7 @@ impl Setter for Inputs {
         if self.0.contains_key(&input_type) {
             return self.0[&input_type].set(path, value);
         }
-        let mut config = Input { channel: u8::MAX, scale: 100 };
+        let mut config = Input::new(u8::MAX);
         config.set(path, value)?;
         self.0.insert(input_type, config).ok();
         Ok(())
@@ -51,36 +101,218 @@ impl ToYAML for Inputs {
         for (input_type, config) in self.0.iter() {
             self.write_indent(indent, w)?;
             writeln!(w, "{}:", input_type)?;
-            self.write_indent(indent, w)?;
-            writeln!(w, "  channel: {}", config.channel + 1)?;
-            if config.scale != 100 {
-                self.write_indent(indent, w)?;
-                writeln!(w, "  scale: {}", config.scale)?;
+            config.write_to(indent + 1, w)?;
+        }
+        Ok(())
+    }
+}
+
+// Modes of each switch position, channel range evenly divided by number of positions
+#[derive(Clone, Default)]
+pub struct FlightModes(pub Vec<FlightMode, U6>);
+
+impl FlightModes {
+    pub fn select(&self, value: i16) -> Option<FlightMode> {
+        let size = self.0.len();
+        if size == 0 {
+            return None;
+        }
+        let offset = (value as i32 - i16::MIN as i32) as usize;
+        Some(self.0[offset * size / (u16::MAX as usize + 1)])
+    }
+
+    fn set(&mut self, index: Option<usize>, value: Value) -> Result<(), Error> {
+        let index = match index {
+            Some(index) => index,
+            None => {
+                self.0.clear();
+                return Ok(());
             }
+        };
+        let mode = value.parse()?.ok_or(Error::ExpectValue)?;
+        if index < self.0.len() {
+            self.0[index] = mode;
+        } else if index == self.0.len() {
+            self.0.push(mode).map_err(|_| Error::MalformedPath)?;
+        } else {
+            return Err(Error::MalformedPath);
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for FlightModes {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        for mode in self.0.iter() {
+            self.write_indent(indent, w)?;
+            writeln!(w, "- {}", mode)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FailsafeAction {
+    ReturnToHome,
+    Cut,
+}
+
+impl FromStr for FailsafeAction {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "return-to-home" => Ok(Self::ReturnToHome),
+            "cut" => Ok(Self::Cut),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for FailsafeAction {
+    fn into(self) -> &'static str {
+        match self {
+            Self::ReturnToHome => "return-to-home",
+            Self::Cut => "cut",
+        }
+    }
+}
+
+const DEFAULT_HOLD: u16 = 500;
+const DEFAULT_LEVEL: u16 = 3000;
+const DEFAULT_THROTTLE: u8 = 30;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Failsafe {
+    pub hold: u16,    // milliseconds
+    pub level: u16,   // milliseconds
+    pub throttle: u8, // percentage while leveling
+    pub action: FailsafeAction,
+}
+
+impl Default for Failsafe {
+    fn default() -> Self {
+        Self {
+            hold: DEFAULT_HOLD,
+            level: DEFAULT_LEVEL,
+            throttle: DEFAULT_THROTTLE,
+            action: FailsafeAction::Cut,
+        }
+    }
+}
+
+impl Setter for Failsafe {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "hold" => self.hold = value.parse()?.unwrap_or(DEFAULT_HOLD),
+            "level" => self.level = value.parse()?.unwrap_or(DEFAULT_LEVEL),
+            "throttle" => self.throttle = value.parse()?.unwrap_or(DEFAULT_THROTTLE),
+            "action" => self.action = value.parse()?.unwrap_or(FailsafeAction::Cut),
+            _ => return Err(Error::MalformedPath),
         }
         Ok(())
     }
 }
 
+impl ToYAML for Failsafe {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "hold: {}", self.hold)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "level: {}", self.level)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "throttle: {}", self.throttle)?;
+        self.write_indent(indent, w)?;
+        let action: &str = self.action.into();
+        writeln!(w, "action: {}", action)
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct Receiver {
     pub inputs: Inputs,
+    pub flight_modes: FlightModes,
+    pub failsafe: Failsafe,
 }
 
 impl ToYAML for Receiver {
     fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
         self.write_indent(indent, w)?;
         writeln!(w, "inputs:")?;
-        self.inputs.write_to(indent + 1, w)
+        self.inputs.write_to(indent + 1, w)?;
+
+        if self.flight_modes.0.len() > 0 {
+            self.write_indent(indent, w)?;
+            writeln!(w, "flight-modes:")?;
+            self.flight_modes.write_to(indent + 1, w)?;
+        }
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "failsafe:")?;
+        self.failsafe.write_to(indent + 1, w)
     }
 }
 
 impl Setter for Receiver {
     fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
-        let key = path.next().ok_or(Error::MalformedPath)?;
-        if key != "inputs" {
-            return Err(Error::MalformedPath);
+        match split_index(path.next().ok_or(Error::MalformedPath)?)? {
+            ("inputs", None) => self.inputs.set(path, value),
+            ("flight-modes", index) => self.flight_modes.set(index, value),
+            ("failsafe", None) => self.failsafe.set(path, value),
+            _ => Err(Error::MalformedPath),
         }
-        self.inputs.set(path, value)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_flight_mode_switch() {
+        use crate::datastructures::flight_mode::FlightMode;
+
+        use super::FlightModes;
+
+        let mut modes = FlightModes::default();
+        assert_eq!(modes.select(0), None);
+        modes.0.push(FlightMode::Manual).ok();
+        modes.0.push(FlightMode::Stabilized).ok();
+        modes.0.push(FlightMode::Acro).ok();
+        assert_eq!(modes.select(i16::MIN), Some(FlightMode::Manual));
+        assert_eq!(modes.select(-12000), Some(FlightMode::Manual));
+        assert_eq!(modes.select(0), Some(FlightMode::Stabilized));
+        assert_eq!(modes.select(12000), Some(FlightMode::Acro));
+        assert_eq!(modes.select(i16::MAX), Some(FlightMode::Acro));
+    }
+
+    #[test]
+    fn test_input_shaping_yaml() {
+        extern crate std;
+
+        use std::string::String;
+
+        use crate::config::yaml::{ToYAML, YamlParser};
+        use crate::datastructures::input::InputType;
+
+        use super::{Input, Inputs};
+
+        let yaml = "\
+        roll:\n  \
+          channel: 1\n  \
+          deadband: 5\n  \
+          expo: 30\n  \
+          rate: 80\n  \
+          reversed: true\n  \
+          subtrim: -3\n";
+        let inputs: Inputs = YamlParser::new(yaml).parse();
+        let mut expected = Input::new(0);
+        expected.deadband = 5;
+        expected.expo = 30;
+        expected.rate = 80;
+        expected.reversed = true;
+        expected.subtrim = -3;
+        assert_eq!(inputs.0.get(&InputType::Roll), Some(&expected));
+
+        let mut buf = String::new();
+        inputs.write_to(0, &mut buf).ok();
+        assert_eq!(buf, yaml);
     }
 }
@@ -33,6 +33,19 @@ impl core::fmt::Display for Error {
     }
 }
 
+/// Splits sequence key like `key[0]` into key and index
+pub fn split_index(key: &str) -> Result<(&str, Option<usize>), Error> {
+    let begin = match key.find('[') {
+        Some(begin) => begin,
+        None => return Ok((key, None)),
+    };
+    if !key.ends_with(']') {
+        return Err(Error::MalformedPath);
+    }
+    let index = key[begin + 1..key.len() - 1].parse().map_err(|_| Error::MalformedPath)?;
+    Ok((&key[..begin], Some(index)))
+}
+
 pub trait Setter {
     fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error>;
 }
@@ -0,0 +1,157 @@
+use core::fmt::Write;
+use core::str::Split;
+
+use crate::datastructures::decimal::IntegerDecimal;
+
+use super::setter::{Error, Setter, Value};
+use super::yaml::ToYAML;
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct PID {
+    pub kp: IntegerDecimal,
+    pub ki: IntegerDecimal,
+    pub kd: IntegerDecimal,
+    pub i_limit: IntegerDecimal,
+}
+
+impl Setter for PID {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "kp" => self.kp = value.parse()?.unwrap_or_default(),
+            "ki" => self.ki = value.parse()?.unwrap_or_default(),
+            "kd" => self.kd = value.parse()?.unwrap_or_default(),
+            "i-limit" => self.i_limit = value.parse()?.unwrap_or_default(),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for PID {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "kp: {}", self.kp)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "ki: {}", self.ki)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "kd: {}", self.kd)?;
+        self.write_indent(indent, w)?;
+        writeln!(w, "i-limit: {}", self.i_limit)
+    }
+}
+
+const DEFAULT_ANGLE_PID: PID = PID {
+    kp: integer_decimal!(4_0, 1),
+    ki: integer_decimal!(0, 0),
+    kd: integer_decimal!(0, 0),
+    i_limit: integer_decimal!(0, 0),
+};
+const DEFAULT_RATE_PID: PID = PID {
+    kp: integer_decimal!(0_5, 1),
+    ki: integer_decimal!(0_1, 1),
+    kd: integer_decimal!(0_01, 2),
+    i_limit: integer_decimal!(0_3, 1),
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Axis {
+    pub angle: PID,
+    pub rate: PID,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Self { angle: DEFAULT_ANGLE_PID, rate: DEFAULT_RATE_PID }
+    }
+}
+
+impl Setter for Axis {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "angle" => self.angle.set(path, value),
+            "rate" => self.rate.set(path, value),
+            _ => Err(Error::MalformedPath),
+        }
+    }
+}
+
+impl ToYAML for Axis {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "angle:")?;
+        self.angle.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "rate:")?;
+        self.rate.write_to(indent + 1, w)
+    }
+}
+
+const DEFAULT_MAX_RATE: u16 = 180;
+const DEFAULT_MAX_ANGLE: u8 = 30;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Stabilizer {
+    pub anti_windup: bool,
+    pub max_rate: u16, // degree per second
+    pub max_angle: u8, // degree
+    pub roll: Axis,
+    pub pitch: Axis,
+    pub yaw_rate: PID,
+}
+
+impl Default for Stabilizer {
+    fn default() -> Self {
+        Self {
+            anti_windup: true,
+            max_rate: DEFAULT_MAX_RATE,
+            max_angle: DEFAULT_MAX_ANGLE,
+            roll: Axis::default(),
+            pitch: Axis::default(),
+            yaw_rate: DEFAULT_RATE_PID,
+        }
+    }
+}
+
+impl Setter for Stabilizer {
+    fn set(&mut self, path: &mut Split<char>, value: Value) -> Result<(), Error> {
+        match path.next().ok_or(Error::MalformedPath)? {
+            "anti-windup" => self.anti_windup = value.parse()?.unwrap_or(true),
+            "max-rate" => self.max_rate = value.parse()?.unwrap_or(DEFAULT_MAX_RATE),
+            "max-angle" => {
+                let value = value.parse()?.unwrap_or(DEFAULT_MAX_ANGLE);
+                self.max_angle = core::cmp::min(value, 90)
+            }
+            "roll" => return self.roll.set(path, value),
+            "pitch" => return self.pitch.set(path, value),
+            "yaw-rate" => return self.yaw_rate.set(path, value),
+            _ => return Err(Error::MalformedPath),
+        }
+        Ok(())
+    }
+}
+
+impl ToYAML for Stabilizer {
+    fn write_to(&self, indent: usize, w: &mut impl Write) -> core::fmt::Result {
+        self.write_indent(indent, w)?;
+        writeln!(w, "anti-windup: {}", self.anti_windup)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-rate: {}", self.max_rate)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "max-angle: {}", self.max_angle)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "roll:")?;
+        self.roll.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "pitch:")?;
+        self.pitch.write_to(indent + 1, w)?;
+
+        self.write_indent(indent, w)?;
+        writeln!(w, "yaw-rate:")?;
+        self.yaw_rate.write_to(indent + 1, w)
+    }
+}
@@ -0,0 +1,104 @@
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PreArmCheck {
+    GyroNotCalibrated = 0,
+    ConfigNotLoaded,
+    ReceiverStale,
+    ThrottleNotLow,
+    LowBattery,
+    NoGNSSFix,
+    EscCalibrating,
+    NoArmSwitch,
+}
+
+const PRE_ARM_CHECKS: [PreArmCheck; 8] = [
+    PreArmCheck::GyroNotCalibrated,
+    PreArmCheck::ConfigNotLoaded,
+    PreArmCheck::ReceiverStale,
+    PreArmCheck::ThrottleNotLow,
+    PreArmCheck::LowBattery,
+    PreArmCheck::NoGNSSFix,
+    PreArmCheck::EscCalibrating,
+    PreArmCheck::NoArmSwitch,
+];
+
+impl PreArmCheck {
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::GyroNotCalibrated => "GYRO",
+            Self::ConfigNotLoaded => "CFG",
+            Self::ReceiverStale => "RX",
+            Self::ThrottleNotLow => "THR",
+            Self::LowBattery => "BATT",
+            Self::NoGNSSFix => "GPS",
+            Self::EscCalibrating => "ESC",
+            Self::NoArmSwitch => "ARM",
+        }
+    }
+}
+
+impl Into<&str> for PreArmCheck {
+    fn into(self) -> &'static str {
+        match self {
+            Self::GyroNotCalibrated => "gyro-not-calibrated",
+            Self::ConfigNotLoaded => "config-not-loaded",
+            Self::ReceiverStale => "receiver-stale",
+            Self::ThrottleNotLow => "throttle-not-low",
+            Self::LowBattery => "low-battery",
+            Self::NoGNSSFix => "no-gnss-fix",
+            Self::EscCalibrating => "esc-calibrating",
+            Self::NoArmSwitch => "no-arm-switch",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct PreArmFailures(pub u8);
+
+impl PreArmFailures {
+    pub fn insert(&mut self, check: PreArmCheck) {
+        self.0 |= 1 << check as u8;
+    }
+
+    pub fn contains(&self, check: PreArmCheck) -> bool {
+        self.0 & (1 << check as u8) > 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = PreArmCheck> {
+        PRE_ARM_CHECKS.iter().filter(move |&&check| self.contains(check)).map(|&check| check)
+    }
+}
+
+impl sval::value::Value for PreArmFailures {
+    fn stream(&self, stream: &mut sval::value::Stream) -> sval::value::Result {
+        stream.seq_begin(None)?;
+        for check in self.iter() {
+            let s: &str = check.into();
+            stream.seq_elem(s)?;
+        }
+        stream.seq_end()
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug, Value)]
+pub struct Arming {
+    pub armed: bool,
+    pub failures: PreArmFailures,
+}
+
+impl core::fmt::Display for Arming {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.armed {
+            return write!(f, "armed");
+        }
+        write!(f, "disarmed")?;
+        for (i, check) in self.failures.iter().enumerate() {
+            let s: &str = check.into();
+            write!(f, "{}{}", if i == 0 { ", pre-arm failures: " } else { ", " }, s)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum EscCalibration {
+    Idle,
+    Max,
+    Min,
+}
+
+impl Default for EscCalibration {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
@@ -0,0 +1,52 @@
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FailsafeStage {
+    Normal,
+    Hold,
+    Level,
+    ReturnToHome,
+    Cut,
+}
+
+impl Default for FailsafeStage {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl FailsafeStage {
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::Normal => "",
+            Self::Hold => "FS HOLD",
+            Self::Level => "FS LEVEL",
+            Self::ReturnToHome => "FS RTH",
+            Self::Cut => "FS CUT",
+        }
+    }
+}
+
+impl Into<&str> for FailsafeStage {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Hold => "hold",
+            Self::Level => "level",
+            Self::ReturnToHome => "return-to-home",
+            Self::Cut => "cut",
+        }
+    }
+}
+
+impl core::fmt::Display for FailsafeStage {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+impl sval::value::Value for FailsafeStage {
+    fn stream(&self, stream: &mut sval::value::Stream) -> sval::value::Result {
+        let s: &str = (*self).into();
+        stream.any(s)
+    }
+}
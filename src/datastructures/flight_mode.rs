@@ -0,0 +1,87 @@
+use core::str::FromStr;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FlightMode {
+    Manual,
+    Stabilized,
+    Acro,
+    AltitudeHold,
+    HeadingHold,
+    ReturnToHome,
+    Loiter,
+}
+
+impl Default for FlightMode {
+    fn default() -> Self {
+        Self::Manual
+    }
+}
+
+impl FlightMode {
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::Manual => "MAN",
+            Self::Stabilized => "STAB",
+            Self::Acro => "ACRO",
+            Self::AltitudeHold => "ALT",
+            Self::HeadingHold => "HDG",
+            Self::ReturnToHome => "RTH",
+            Self::Loiter => "LOIT",
+        }
+    }
+}
+
+impl FromStr for FlightMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "manual" => Ok(Self::Manual),
+            "stabilized" => Ok(Self::Stabilized),
+            "acro" => Ok(Self::Acro),
+            "altitude-hold" => Ok(Self::AltitudeHold),
+            "heading-hold" => Ok(Self::HeadingHold),
+            "return-to-home" => Ok(Self::ReturnToHome),
+            "loiter" => Ok(Self::Loiter),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<&str> for FlightMode {
+    fn into(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Stabilized => "stabilized",
+            Self::Acro => "acro",
+            Self::AltitudeHold => "altitude-hold",
+            Self::HeadingHold => "heading-hold",
+            Self::ReturnToHome => "return-to-home",
+            Self::Loiter => "loiter",
+        }
+    }
+}
+
+impl core::fmt::Display for FlightMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+impl sval::value::Value for FlightMode {
+    fn stream(&self, stream: &mut sval::value::Stream) -> sval::value::Result {
+        let s: &str = (*self).into();
+        stream.any(s)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_default_flight_mode() {
+        use super::FlightMode;
+
+        // No mode switch configured, control surfaces follow stick directly
+        assert_eq!(FlightMode::default(), FlightMode::Manual);
+        assert_eq!("manual".parse(), Ok(FlightMode::default()));
+    }
+}
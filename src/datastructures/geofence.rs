@@ -0,0 +1,51 @@
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Breach {
+    None,
+    Altitude,
+    Distance,
+    Inclusion, // outside every inclusion zone
+    Exclusion, // inside some exclusion zone
+}
+
+impl Default for Breach {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl Breach {
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Altitude => "FENCE ALT",
+            Self::Distance => "FENCE DIST",
+            Self::Inclusion | Self::Exclusion => "FENCE",
+        }
+    }
+}
+
+impl Into<&str> for Breach {
+    fn into(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Altitude => "altitude",
+            Self::Distance => "distance",
+            Self::Inclusion => "inclusion",
+            Self::Exclusion => "exclusion",
+        }
+    }
+}
+
+impl core::fmt::Display for Breach {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+impl sval::value::Value for Breach {
+    fn stream(&self, stream: &mut sval::value::Stream) -> sval::value::Result {
+        let s: &str = (*self).into();
+        stream.any(s)
+    }
+}
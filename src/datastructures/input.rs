@@ -2,12 +2,24 @@ use core::str::FromStr;
 
 pub type RSSI = u16;
 
+pub const MAX_AUX: usize = 12;
+
+const AUX_NAMES: [&str; MAX_AUX] = [
+    "aux1", "aux2", "aux3", "aux4", "aux5", "aux6", "aux7", "aux8", "aux9", "aux10", "aux11",
+    "aux12",
+];
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum InputType {
-    Throttle = 0,
+    Throttle,
     Roll,
     Pitch,
     Yaw,
+    Mode,
+    Arm,
+    Flaps,
+    Gear,
+    Aux(u8), // [1, MAX_AUX]
 }
 
 impl FromStr for InputType {
@@ -18,7 +30,14 @@ impl FromStr for InputType {
             "roll" => Ok(Self::Roll),
             "pitch" => Ok(Self::Pitch),
             "yaw" => Ok(Self::Yaw),
-            _ => Err(()),
+            "mode" => Ok(Self::Mode),
+            "arm" => Ok(Self::Arm),
+            "flaps" => Ok(Self::Flaps),
+            "gear" => Ok(Self::Gear),
+            _ => match AUX_NAMES.iter().position(|&name| name == string) {
+                Some(index) => Ok(Self::Aux(index as u8 + 1)),
+                None => Err(()),
+            },
         }
     }
 }
@@ -30,6 +49,11 @@ impl Into<&str> for InputType {
             Self::Roll => "roll",
             Self::Pitch => "pitch",
             Self::Yaw => "yaw",
+            Self::Mode => "mode",
+            Self::Arm => "arm",
+            Self::Flaps => "flaps",
+            Self::Gear => "gear",
+            Self::Aux(index) => AUX_NAMES[index as usize - 1],
         }
     }
 }
@@ -45,16 +69,55 @@ pub type Throttle = i16;
 pub type Roll = i16;
 pub type Pitch = i16;
 pub type Yaw = i16;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Flaps {
     Auto,
     Half,
     Full,
 }
+
+impl Default for Flaps {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl From<i16> for Flaps {
+    // 3-position switch
+    fn from(value: i16) -> Self {
+        if value < i16::MIN / 3 {
+            Self::Auto
+        } else if value < i16::MAX / 3 {
+            Self::Half
+        } else {
+            Self::Full
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LandingGear {
     Up,
     Down,
 }
 
+impl Default for LandingGear {
+    fn default() -> Self {
+        Self::Down
+    }
+}
+
+impl From<i16> for LandingGear {
+    fn from(value: i16) -> Self {
+        if value > 0 {
+            Self::Up
+        } else {
+            Self::Down
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Value)]
 pub struct ControlInput {
     pub throttle: Throttle,
@@ -69,7 +132,31 @@ impl Default for ControlInput {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default)]
 pub struct FixedWingInput {
     pub flaps: Flaps,
     pub landing_gear: LandingGear,
 }
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AuxiliaryInput {
+    pub aux: [i16; MAX_AUX],
+    pub fixed_wing: FixedWingInput,
+}
+
+mod test {
+    #[test]
+    fn test_input_type() {
+        use super::{Flaps, InputType, LandingGear};
+
+        assert_eq!("aux1".parse(), Ok(InputType::Aux(1)));
+        assert_eq!("aux12".parse(), Ok(InputType::Aux(12)));
+        assert_eq!("aux13".parse::<InputType>(), Err(()));
+        let name: &str = InputType::Aux(10).into();
+        assert_eq!(name, "aux10");
+        assert_eq!(Flaps::from(i16::MIN), Flaps::Auto);
+        assert_eq!(Flaps::from(0), Flaps::Half);
+        assert_eq!(Flaps::from(i16::MAX), Flaps::Full);
+        assert_eq!(LandingGear::from(i16::MAX), LandingGear::Up);
+    }
+}
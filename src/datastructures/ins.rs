@@ -0,0 +1,7 @@
+/// Whether INS estimation uncertainty is within navigation limits
+#[derive(Copy, Clone, Debug, Default, PartialEq, Value)]
+pub struct Validity {
+    pub horizontal: bool, // east and north position
+    pub vertical: bool,   // altitude
+    pub velocity: bool,
+}
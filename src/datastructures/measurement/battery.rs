@@ -1,3 +1,7 @@
+// Lower reading means battery not connected or voltage not sampled yet
+const MIN_CELL_VOLTAGE: u16 = 3000;
+const MAX_CELL_VOLTAGE: u16 = 4200;
+
 #[derive(Copy, Clone, Default, Debug)]
 pub struct Battery(pub u16); // unit of milli voltage
 
@@ -27,6 +31,14 @@ impl Into<u16> for Battery {
 }
 
 impl Battery {
+    /// LiPo cell count detected from pack voltage, none while voltage too low to tell
+    pub fn cells(&self) -> Option<u8> {
+        if self.0 < MIN_CELL_VOLTAGE {
+            return None;
+        }
+        Some(core::cmp::min(self.0 / MAX_CELL_VOLTAGE + 1, 8) as u8)
+    }
+
     pub fn percentage(&self) -> u8 {
         let result = match self.0 {
             0..=3290 => 0,
@@ -64,4 +76,16 @@ mod test {
         assert_eq!(Battery(3400).percentage(), 2);
         assert_eq!(Battery(3300).percentage(), 0);
     }
+
+    #[test]
+    fn test_battery_cells() {
+        use super::Battery;
+
+        assert_eq!(Battery(0).cells(), None);
+        assert_eq!(Battery(2000).cells(), None);
+        assert_eq!(Battery(3700).cells(), Some(1));
+        assert_eq!(Battery(11100).cells(), Some(3));
+        assert_eq!(Battery(16000).cells(), Some(4));
+        assert_eq!(Battery(50000).cells(), Some(8));
+    }
 }
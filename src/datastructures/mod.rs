@@ -1,10 +1,16 @@
 use core::str::FromStr;
 
+pub mod arming;
+pub mod calibration;
 pub mod coordinate;
 pub mod data_source;
 #[macro_use]
 pub mod decimal;
+pub mod failsafe;
+pub mod flight_mode;
+pub mod geofence;
 pub mod input;
+pub mod ins;
 pub mod measurement;
 pub mod waypoint;
 
@@ -12,8 +12,24 @@ impl Default for Waypoint {
     }
 }
 
+/// Altitude is captured on first navigation update,
+/// latitude and longitude on first GNSS fix
+#[derive(Copy, Clone, Debug, Default, Value)]
+pub struct Home {
+    pub position: Position,
+    pub altitude_set: bool,
+    pub fixed: bool, // latitude and longitude fixed by GNSS
+}
+
 #[derive(Copy, Clone, Debug, Default, Value)]
 pub struct Steerpoint {
     pub index: u8,
     pub waypoint: Waypoint,
 }
+
+/// Lateral guidance towards steerpoint
+#[derive(Copy, Clone, Debug, Default, Value)]
+pub struct Guidance {
+    pub course: u16, // degree, desired course over ground
+    pub bank: f32,   // radian, positive banks right
+}
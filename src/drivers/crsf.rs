@@ -0,0 +1,197 @@
+use super::receiver::Receiver;
+
+pub const CRSF_SYNC: u8 = 0xC8;
+pub const CRSF_BAUDRATE: u32 = 420_000;
+pub const MAX_FRAME_SIZE: usize = 64;
+const NUM_CHANNELS: usize = 16;
+
+pub const FRAME_TYPE_GPS: u8 = 0x02;
+pub const FRAME_TYPE_VARIO: u8 = 0x07;
+pub const FRAME_TYPE_BATTERY_SENSOR: u8 = 0x08;
+pub const FRAME_TYPE_LINK_STATISTICS: u8 = 0x14;
+pub const FRAME_TYPE_RC_CHANNELS_PACKED: u8 = 0x16;
+pub const FRAME_TYPE_ATTITUDE: u8 = 0x1E;
+pub const FRAME_TYPE_FLIGHT_MODE: u8 = 0x21;
+
+// CRC8 DVB-S2 over frame type and payload
+pub fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 > 0 { (crc << 1) ^ 0xD5 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// 16 channels packed as 11 bits little endian
+fn unpack_channels(payload: &[u8]) -> [u16; NUM_CHANNELS] {
+    let mut channels = [0u16; NUM_CHANNELS];
+    let (mut bits, mut num_bits, mut index) = (0u32, 0usize, 0usize);
+    for &byte in payload.iter() {
+        bits |= (byte as u32) << num_bits;
+        num_bits += 8;
+        if num_bits >= 11 {
+            channels[index] = (bits & 0x7FF) as u16;
+            bits >>= 11;
+            num_bits -= 11;
+            index += 1;
+            if index >= NUM_CHANNELS {
+                break;
+            }
+        }
+    }
+    channels
+}
+
+/// Returns frame size, payload no longer than MAX_FRAME_SIZE - 4
+pub fn encode_frame(frame_type: u8, payload: &[u8], buffer: &mut [u8; MAX_FRAME_SIZE]) -> usize {
+    let size = payload.len();
+    buffer[0] = CRSF_SYNC;
+    buffer[1] = (size + 2) as u8; // type + payload + crc
+    buffer[2] = frame_type;
+    buffer[3..3 + size].copy_from_slice(payload);
+    buffer[3 + size] = crc8(&buffer[2..3 + size]);
+    4 + size
+}
+
+#[inline]
+fn to_axis(value: u16) -> i32 {
+    // [172, 1811] -> [-820, 819] -> [-32768, 32728]
+    (value as i32 - 992) * 32768 / 820
+}
+
+pub struct CrsfReceiver {
+    buffer: [u8; MAX_FRAME_SIZE],
+    size: usize,
+    receiver: Receiver,
+}
+
+impl CrsfReceiver {
+    pub fn new() -> Self {
+        Self { buffer: [0u8; MAX_FRAME_SIZE], size: 0, receiver: Receiver::new() }
+    }
+
+    pub fn receiver(&mut self) -> &mut Receiver {
+        &mut self.receiver
+    }
+
+    fn handle_frame(&mut self, frame_type: u8, payload: &[u8]) {
+        match frame_type {
+            FRAME_TYPE_RC_CHANNELS_PACKED if payload.len() >= 22 => {
+                self.receiver.write_channels(&unpack_channels(payload), to_axis)
+            }
+            FRAME_TYPE_LINK_STATISTICS if payload.len() >= 10 => {
+                // uplink rssi ant1, rssi ant2, link quality, snr, ...
+                self.receiver.write_rssi(payload[2] as u16)
+            }
+            _ => (),
+        }
+    }
+
+    fn receive(&mut self, byte: u8) {
+        if self.size == 0 && byte != CRSF_SYNC {
+            return;
+        }
+        self.buffer[self.size] = byte;
+        self.size += 1;
+        if self.size < 2 {
+            return;
+        }
+        let length = self.buffer[1] as usize; // type + payload + crc
+        if length < 2 || 2 + length > MAX_FRAME_SIZE {
+            self.size = 0;
+            return;
+        }
+        if self.size < 2 + length {
+            return;
+        }
+        let frame = &self.buffer[2..2 + length];
+        let (frame_type, crc) = (frame[0], frame[length - 1]);
+        if crc8(&frame[..length - 1]) == crc {
+            let mut payload = [0u8; MAX_FRAME_SIZE];
+            payload[..length - 2].copy_from_slice(&frame[1..length - 1]);
+            self.handle_frame(frame_type, &payload[..length - 2]);
+        }
+        self.size = 0;
+    }
+
+    pub fn handle(&mut self, ring: &[u8], half: bool) {
+        let begin = if half { 0 } else { ring.len() / 2 };
+        let end = if half { ring.len() / 2 } else { ring.len() };
+        for i in begin..end {
+            self.receive(ring[i]);
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_crsf_channels() {
+        use super::{crc8, unpack_channels};
+
+        assert_eq!(crc8(&[0x16]), 0xD3);
+        let mut payload = [0u8; 22];
+        payload[0] = 0xAC; // channel 1 = 172, channel 2 = 1811
+        payload[1] = 0x98;
+        payload[2] = 0x38;
+        let channels = unpack_channels(&payload);
+        assert_eq!(channels[0], 172);
+        assert_eq!(channels[1], 1811);
+        assert_eq!(channels[2], 0);
+    }
+
+    #[test]
+    fn test_crsf_encode_frame() {
+        use super::{encode_frame, FRAME_TYPE_VARIO, MAX_FRAME_SIZE};
+
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        let size = encode_frame(FRAME_TYPE_VARIO, &[0xFF, 0x38], &mut buffer);
+        assert_eq!(&buffer[..size], &[0xC8, 4, 0x07, 0xFF, 0x38, 0x84]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_crsf_frame() {
+        use crate::config::receiver::Input;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::{OptionData, StaticData};
+        use crate::datastructures::input::InputType;
+
+        use super::{crc8, CrsfReceiver, CRSF_SYNC};
+
+        let mut config = Config::default();
+        config.receiver.inputs.0.insert(InputType::Roll, Input::new(1)).ok();
+        config::replace(config);
+
+        let mut crsf = CrsfReceiver::new();
+        let mut input = crsf.receiver().input_reader();
+        let mut rssi = crsf.receiver().rssi_reader();
+
+        let mut frame = [0u8; 26];
+        frame[0] = CRSF_SYNC;
+        frame[1] = 24;
+        frame[2] = 0x16;
+        frame[3] = 0xAC;
+        frame[4] = 0x98;
+        frame[5] = 0x38;
+        frame[25] = crc8(&frame[2..25]);
+        let mut link = [CRSF_SYNC, 12, 0x14, 50, 50, 87, 10, 0, 0, 0, 0, 0, 0, 0];
+        link[13] = crc8(&link[2..13]);
+
+        let mut ring = [0u8; 64];
+        ring[20..46].copy_from_slice(&frame);
+        ring[46..60].copy_from_slice(&link);
+        crsf.handle(&ring, true);
+        assert!(OptionData::read(&mut input).is_none());
+        crsf.handle(&ring, false);
+        assert_eq!(OptionData::read(&mut input).unwrap().roll, 32728);
+        assert_eq!(StaticData::read(&mut rssi), 87);
+
+        ring[30] ^= 0xFF; // corrupted
+        crsf.handle(&ring, true);
+        crsf.handle(&ring, false);
+        assert!(OptionData::read(&mut input).is_none());
+    }
+}
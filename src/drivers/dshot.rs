@@ -0,0 +1,128 @@
+pub const MIN_THROTTLE: u16 = 48;
+pub const MAX_THROTTLE: u16 = 2047;
+/// 16 frame bits followed by 2 low bits keeping line idle between frames
+pub const BUFFER_SIZE: usize = 18;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Command {
+    MotorStop = 0,
+    Beep1 = 1,
+    Beep2 = 2,
+    Beep3 = 3,
+    Beep4 = 4,
+    Beep5 = 5,
+    ESCInfo = 6,
+    SpinDirection1 = 7,
+    SpinDirection2 = 8,
+    Mode3DOff = 9,
+    Mode3DOn = 10,
+    SaveSettings = 12,
+    SpinDirectionNormal = 20,
+    SpinDirectionReversed = 21,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self::MotorStop
+    }
+}
+
+impl Command {
+    /// Settings commands must be sent with telemetry bit set and repeated
+    /// before ESC accepts them
+    pub fn repeat(self) -> usize {
+        match self {
+            Self::MotorStop | Self::ESCInfo => 1,
+            Self::Beep1 | Self::Beep2 | Self::Beep3 | Self::Beep4 | Self::Beep5 => 1,
+            _ => 6,
+        }
+    }
+
+    pub fn frame(self) -> u16 {
+        encode(self as u16, self.repeat() > 1)
+    }
+}
+
+impl core::str::FromStr for Command {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "stop" => Ok(Self::MotorStop),
+            "beep" | "beep1" => Ok(Self::Beep1),
+            "beep2" => Ok(Self::Beep2),
+            "beep3" => Ok(Self::Beep3),
+            "beep4" => Ok(Self::Beep4),
+            "beep5" => Ok(Self::Beep5),
+            "info" => Ok(Self::ESCInfo),
+            "3d-off" => Ok(Self::Mode3DOff),
+            "3d-on" => Ok(Self::Mode3DOn),
+            "save" => Ok(Self::SaveSettings),
+            "normal" => Ok(Self::SpinDirectionNormal),
+            "reversed" => Ok(Self::SpinDirectionReversed),
+            _ => Err(()),
+        }
+    }
+}
+
+fn checksum(packet: u16) -> u16 {
+    (packet ^ (packet >> 4) ^ (packet >> 8)) & 0xF
+}
+
+/// Frame layout: 11 bits value, 1 bit telemetry request, 4 bits CRC
+pub fn encode(value: u16, telemetry: bool) -> u16 {
+    let packet = (value & 0x7FF) << 1 | telemetry as u16;
+    packet << 4 | checksum(packet)
+}
+
+/// Maps full throttle range to [48, 2047], values below 48 are reserved for commands
+pub fn to_dshot_throttle(value: i16) -> u16 {
+    let throttle = (value as i32 - i16::MIN as i32) as u32; // [0, 65535]
+    let range = (MAX_THROTTLE - MIN_THROTTLE) as u32;
+    MIN_THROTTLE + (throttle * range / u16::MAX as u32) as u16
+}
+
+/// Expand frame into timer compare values, MSB first,
+/// high time of bit 1 is 75% of bit period and bit 0 is 37.5%
+pub fn to_dma_buffer(frame: u16, max_duty: u16, buffer: &mut [u16; BUFFER_SIZE]) {
+    let bit1 = (max_duty as u32 * 3 / 4) as u16;
+    let bit0 = (max_duty as u32 * 3 / 8) as u16;
+    for i in 0..16 {
+        buffer[i] = if frame & (0x8000 >> i) > 0 { bit1 } else { bit0 };
+    }
+    buffer[16] = 0;
+    buffer[17] = 0;
+}
+
+mod test {
+    #[test]
+    fn test_dshot_encode() {
+        use super::{encode, Command};
+
+        assert_eq!(encode(1046, false), 0b1000001011000110);
+        assert_eq!(encode(1046, true), 0b1000001011010111);
+        assert_eq!(encode(0, false), 0);
+        assert_eq!(Command::Beep1.frame(), 0b0000000000100010);
+        assert_eq!(Command::SaveSettings.frame(), 0b0000000110011000);
+        assert_eq!(Command::SaveSettings.repeat(), 6);
+    }
+
+    #[test]
+    fn test_to_dshot_throttle() {
+        use super::to_dshot_throttle;
+
+        assert_eq!(to_dshot_throttle(i16::MIN), 48);
+        assert_eq!(to_dshot_throttle(0), 1047);
+        assert_eq!(to_dshot_throttle(i16::MAX), 2047);
+    }
+
+    #[test]
+    fn test_dshot_dma_buffer() {
+        use super::{encode, to_dma_buffer, BUFFER_SIZE};
+
+        let mut buffer = [0u16; BUFFER_SIZE];
+        to_dma_buffer(encode(1046, false), 80, &mut buffer);
+        let expected = [60, 30, 30, 30, 30, 30, 60, 30, 60, 60, 30, 30, 30, 60, 60, 30, 0, 0];
+        assert_eq!(buffer, expected);
+    }
+}
@@ -0,0 +1,107 @@
+use super::receiver::Receiver;
+
+pub const IBUS_BAUDRATE: u32 = 115_200;
+const IBUS_PACKET_SIZE: usize = 32;
+const IBUS_HEADER: [u8; 2] = [0x20, 0x40];
+const NUM_CHANNELS: usize = 14;
+
+pub struct IbusReceiver {
+    receiver: Receiver,
+}
+
+#[inline]
+fn to_axis(value: u16) -> i32 {
+    // [1000, 2000] -> [-500, 500] -> [-32768, 32768]
+    (value as i32 - 1500) * 32768 / 500
+}
+
+// Checksum is 0xFFFF minus sum of all preceding bytes
+fn verify(packet: &[u8; IBUS_PACKET_SIZE]) -> bool {
+    let sum = packet[..30].iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+    u16::from_le_bytes([packet[30], packet[31]]) == 0xFFFF - sum
+}
+
+impl IbusReceiver {
+    pub fn new() -> Self {
+        Self { receiver: Receiver::new() }
+    }
+
+    pub fn receiver(&mut self) -> &mut Receiver {
+        &mut self.receiver
+    }
+
+    fn handle_packet(&mut self, packet: &[u8; IBUS_PACKET_SIZE]) {
+        let mut channels = [0u16; NUM_CHANNELS];
+        for i in 0..NUM_CHANNELS {
+            channels[i] = u16::from_le_bytes([packet[2 + i * 2], packet[3 + i * 2]]) & 0xFFF;
+        }
+        self.receiver.write_rssi(100); // No link quality in IBUS
+        self.receiver.write_channels(&channels, to_axis);
+    }
+
+    pub fn handle(&mut self, ring: &[u8], half: bool) {
+        let begin = if half { 0 } else { ring.len() / 2 };
+        let end = if half { ring.len() / 2 } else { ring.len() };
+        let mut packet = [0u8; IBUS_PACKET_SIZE];
+        for i in begin..end {
+            let index = (i + 1 + ring.len() - IBUS_PACKET_SIZE) % ring.len();
+            if ring[index] != IBUS_HEADER[0] || ring[(index + 1) % ring.len()] != IBUS_HEADER[1] {
+                continue;
+            }
+            for j in 0..IBUS_PACKET_SIZE {
+                packet[j] = ring[(index + j) % ring.len()];
+            }
+            if verify(&packet) {
+                self.handle_packet(&packet);
+                return;
+            }
+        }
+    }
+}
+
+mod test {
+    #[test]
+    #[serial]
+    fn test_ibus_packet() {
+        use crate::config::receiver::Input;
+        use crate::config::{self, Config};
+        use crate::datastructures::data_source::OptionData;
+        use crate::datastructures::input::InputType;
+
+        use super::IbusReceiver;
+
+        let mut config = Config::default();
+        config.receiver.inputs.0.insert(InputType::Throttle, Input::new(2)).ok();
+        config.receiver.inputs.0.insert(InputType::Pitch, Input::new(1)).ok();
+        config::replace(config);
+
+        let mut ibus = IbusReceiver::new();
+        let mut input = ibus.receiver().input_reader();
+
+        let mut packet = [0u8; 32];
+        packet[0] = 0x20;
+        packet[1] = 0x40;
+        for i in 0..14 {
+            packet[2 + i * 2..4 + i * 2].copy_from_slice(&1500u16.to_le_bytes());
+        }
+        packet[4..6].copy_from_slice(&1750u16.to_le_bytes());
+        packet[6..8].copy_from_slice(&1000u16.to_le_bytes());
+        let sum = packet[..30].iter().fold(0u16, |sum, &b| sum + b as u16);
+        packet[30..32].copy_from_slice(&(0xFFFF - sum).to_le_bytes());
+
+        // packet wraps around ring end
+        let mut ring = [0u8; 64];
+        for i in 0..32 {
+            ring[(50 + i) % 64] = packet[i];
+        }
+        ibus.handle(&ring, false);
+        assert!(OptionData::read(&mut input).is_none());
+        ibus.handle(&ring, true);
+        let control_input = OptionData::read(&mut input).unwrap();
+        assert_eq!((control_input.pitch, control_input.throttle), (16384, i16::MIN));
+
+        ring[60] ^= 0x01; // checksum mismatch
+        ibus.handle(&ring, true);
+        assert!(OptionData::read(&mut input).is_none());
+    }
+}
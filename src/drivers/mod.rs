@@ -1,10 +1,14 @@
 pub mod accelerometer;
 pub mod barometer;
+pub mod crsf;
+pub mod dshot;
 pub mod gnss;
 pub mod gyroscope;
+pub mod ibus;
 pub mod magnetometer;
 pub mod max7456;
 pub mod mpu6000;
+pub mod receiver;
 pub mod sbus;
 pub mod sdcard;
 pub mod serial;
@@ -0,0 +1,163 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+use crate::components::event::Notify;
+use crate::config;
+use crate::config::receiver::Input;
+use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
+use crate::datastructures::data_source::u16_source::{U16Data, U16DataSource};
+use crate::datastructures::data_source::DataWriter;
+use crate::datastructures::flight_mode::FlightMode;
+use crate::datastructures::input::{AuxiliaryInput, ControlInput, InputType, RSSI};
+
+/// Data sources shared by RC receivers, channels mapped to inputs by receiver config
+pub struct Receiver {
+    rssi: Rc<U16Data<RSSI>>,
+    control_input: Rc<SingularData<ControlInput>>,
+    flight_mode: Rc<SingularData<FlightMode>>,
+    arm_switch: Rc<SingularData<bool>>,
+    auxiliary: Rc<SingularData<AuxiliaryInput>>,
+    notify: Option<Box<dyn Notify>>,
+}
+
+// Applies scale, reversal, subtrim, deadband, expo then rate
+fn shape(axis: i32, input: &Input) -> i16 {
+    let mut value = (axis * input.scale as i32 / 100) as f32 / 32768.0;
+    if input.reversed {
+        value = -value;
+    }
+    value += input.subtrim as f32 / 100.0;
+    let deadband = input.deadband as f32 / 100.0;
+    value = if value > deadband {
+        (value - deadband) / (1.0 - deadband)
+    } else if value < -deadband {
+        (value + deadband) / (1.0 - deadband)
+    } else {
+        0.0
+    };
+    let expo = input.expo as f32 / 100.0;
+    value = value * (1.0 - expo) + value * value * value * expo;
+    value = value * input.rate as f32 / 100.0;
+    (value * 32768.0).max(i16::MIN as f32).min(i16::MAX as f32) as i16
+}
+
+impl Receiver {
+    pub fn new() -> Self {
+        Self {
+            rssi: Rc::new(U16Data::default()),
+            control_input: Rc::new(SingularData::default()),
+            flight_mode: Rc::new(SingularData::default()),
+            arm_switch: Rc::new(SingularData::default()),
+            auxiliary: Rc::new(SingularData::default()),
+            notify: None,
+        }
+    }
+
+    pub fn rssi_reader(&self) -> U16DataSource<RSSI> {
+        U16DataSource::new(&self.rssi)
+    }
+
+    pub fn input_reader(&self) -> SingularDataSource<ControlInput> {
+        SingularDataSource::new(&self.control_input)
+    }
+
+    pub fn flight_mode_reader(&self) -> SingularDataSource<FlightMode> {
+        SingularDataSource::new(&self.flight_mode)
+    }
+
+    pub fn arm_switch_reader(&self) -> SingularDataSource<bool> {
+        SingularDataSource::new(&self.arm_switch)
+    }
+
+    pub fn auxiliary_reader(&self) -> SingularDataSource<AuxiliaryInput> {
+        SingularDataSource::new(&self.auxiliary)
+    }
+
+    pub fn set_notify(&mut self, notify: Box<dyn Notify>) {
+        self.notify = Some(notify);
+    }
+
+    pub fn write_rssi(&mut self, rssi: RSSI) {
+        self.rssi.write(rssi);
+    }
+
+    /// Raw channel values converted by `to_axis` into [-32768, 32767]
+    pub fn write_channels(&mut self, channels: &[u16], to_axis: impl Fn(u16) -> i32) {
+        let receiver = &config::get().receiver;
+        let mut input = ControlInput::default();
+        let mut auxiliary = AuxiliaryInput::default();
+        for (id, cfg) in receiver.inputs.0.iter() {
+            let channel = cfg.channel as usize;
+            if channel >= channels.len() {
+                continue;
+            }
+            let value = shape(to_axis(channels[channel]), cfg);
+            match id {
+                InputType::Throttle => input.throttle = value,
+                InputType::Roll => input.roll = value,
+                InputType::Pitch => input.pitch = value,
+                InputType::Yaw => input.yaw = value,
+                InputType::Mode => {
+                    if let Some(mode) = receiver.flight_modes.select(value) {
+                        self.flight_mode.write(mode);
+                    }
+                }
+                InputType::Arm => self.arm_switch.write(value > 0),
+                InputType::Flaps => auxiliary.fixed_wing.flaps = value.into(),
+                InputType::Gear => auxiliary.fixed_wing.landing_gear = value.into(),
+                InputType::Aux(index) => auxiliary.aux[*index as usize - 1] = value,
+            }
+        }
+        self.control_input.write(input);
+        self.auxiliary.write(auxiliary);
+        if let Some(ref mut notify) = self.notify {
+            notify.notify()
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_input_shaping() {
+        use crate::config::receiver::Input;
+
+        use super::shape;
+
+        let mut input = Input::new(0);
+        let tests = [(i16::MIN, i16::MIN), (-16384, -16384), (0, 0), (16384, 16384)];
+        for &(axis, expected) in tests.iter() {
+            assert_eq!(shape(axis as i32, &input), expected);
+        }
+        assert_eq!(shape(i16::MAX as i32, &input), i16::MAX);
+        input.scale = 120;
+        assert_eq!(shape(i16::MAX as i32, &input), i16::MAX);
+
+        let mut input = Input::new(0);
+        input.deadband = 10;
+        let tests = [(-3276, 0), (3276, 0), (-32768, -32768), (18023, 16384)];
+        for &(axis, expected) in tests.iter() {
+            assert_eq!(shape(axis, &input), expected);
+        }
+
+        let mut input = Input::new(0);
+        input.expo = 100;
+        let tests = [(-32768, -32768), (-16384, -4096), (0, 0), (16384, 4096)];
+        for &(axis, expected) in tests.iter() {
+            assert_eq!(shape(axis, &input), expected);
+        }
+        input.expo = 50;
+        let tests = [(-32768, -32768), (-16384, -10240), (16384, 10240)];
+        for &(axis, expected) in tests.iter() {
+            assert_eq!(shape(axis, &input), expected);
+        }
+
+        let mut input = Input::new(0);
+        input.rate = 50;
+        input.reversed = true;
+        input.subtrim = 10;
+        let tests = [(-32768, 18022), (0, 1638), (32768, -14745)];
+        for &(axis, expected) in tests.iter() {
+            assert_eq!(shape(axis, &input), expected);
+        }
+    }
+}
@@ -1,22 +1,14 @@
-use alloc::boxed::Box;
-use alloc::rc::Rc;
-
 use sbus_parser::{is_sbus_packet_end, SbusData, SbusPacket, SBUS_PACKET_BEGIN, SBUS_PACKET_SIZE};
 
-use crate::components::event::Notify;
-use crate::config;
-use crate::datastructures::data_source::singular::{SingularData, SingularDataSource};
-use crate::datastructures::data_source::u16_source::{U16Data, U16DataSource};
-use crate::datastructures::data_source::DataWriter;
-use crate::datastructures::input::{ControlInput, InputType, RSSI};
+use crate::datastructures::input::RSSI;
+
+use super::receiver::Receiver;
 
 pub struct SbusReceiver {
     counter: u8,
     loss: u8,
     loss_rate: u8,
-    rssi: Rc<U16Data<RSSI>>,
-    control_input: Rc<SingularData<ControlInput>>,
-    notify: Option<Box<dyn Notify>>,
+    receiver: Receiver,
 }
 
 #[inline]
@@ -25,39 +17,13 @@ fn to_axis(value: u16) -> i32 {
     (value as i32).wrapping_sub(0x400) << 5
 }
 
-fn scale(data: u16, scale: u8) -> i16 {
-    let scaled = to_axis(data) * scale as i32 / 100;
-    if scaled > i16::MAX as i32 {
-        i16::MAX
-    } else if scaled < i16::MIN as i32 {
-        i16::MIN
-    } else {
-        scaled as i16
-    }
-}
-
 impl SbusReceiver {
     pub fn new() -> Self {
-        Self {
-            counter: 0,
-            loss: 0,
-            loss_rate: 0,
-            rssi: Rc::new(U16Data::default()),
-            control_input: Rc::new(SingularData::default()),
-            notify: None,
-        }
-    }
-
-    pub fn rssi_reader(&self) -> U16DataSource<RSSI> {
-        U16DataSource::new(&self.rssi)
-    }
-
-    pub fn input_reader(&self) -> SingularDataSource<ControlInput> {
-        SingularDataSource::new(&self.control_input)
+        Self { counter: 0, loss: 0, loss_rate: 0, receiver: Receiver::new() }
     }
 
-    pub fn set_notify(&mut self, notify: Box<dyn Notify>) {
-        self.notify = Some(notify);
+    pub fn receiver(&mut self) -> &mut Receiver {
+        &mut self.receiver
     }
 
     fn handle_sbus_data(&mut self, data: &SbusData) {
@@ -67,30 +33,11 @@ impl SbusReceiver {
             self.loss_rate = self.loss;
             self.counter = 0;
         }
-        self.rssi.write((100 - self.loss_rate) as RSSI);
-
-        let mut counter = 0;
-        let mut input = ControlInput::default();
-        for (id, cfg) in config::get().receiver.inputs.0.iter() {
-            let channel = cfg.channel as usize;
-            if channel > data.channels.len() {
-                continue;
-            }
-            match id {
-                InputType::Throttle => input.throttle = scale(data.channels[channel], cfg.scale),
-                InputType::Roll => input.roll = scale(data.channels[channel], cfg.scale),
-                InputType::Pitch => input.pitch = scale(data.channels[channel], cfg.scale),
-                InputType::Yaw => input.yaw = scale(data.channels[channel], cfg.scale),
-            }
-            counter += 1;
-            if counter >= 4 {
-                break;
-            }
-        }
-        self.control_input.write(input);
-        if let Some(ref mut notify) = self.notify {
-            notify.notify()
+        self.receiver.write_rssi((100 - self.loss_rate) as RSSI);
+        if data.failsafe {
+            return;
         }
+        self.receiver.write_channels(&data.channels, to_axis);
     }
 
     pub fn handle(&mut self, ring: &[u8], half: bool) {
@@ -1,8 +1,13 @@
+use super::crsf::CrsfReceiver;
 use super::gnss::GNSS;
+use super::ibus::IbusReceiver;
+use super::receiver::Receiver;
 use super::sbus::SbusReceiver;
 
 pub enum Device {
     SBUS(SbusReceiver),
+    CRSF(CrsfReceiver),
+    IBUS(IbusReceiver),
     GNSS(GNSS),
 }
 
@@ -10,7 +15,18 @@ impl Device {
     pub fn handle(&mut self, ring: &[u8], half: bool) {
         match self {
             Device::SBUS(sbus_device) => sbus_device.handle(ring, half),
+            Device::CRSF(crsf_device) => crsf_device.handle(ring, half),
+            Device::IBUS(ibus_device) => ibus_device.handle(ring, half),
             Device::GNSS(gnss) => gnss.handle(ring, half),
         }
     }
+
+    pub fn receiver(&mut self) -> Option<&mut Receiver> {
+        match self {
+            Device::SBUS(sbus_device) => Some(sbus_device.receiver()),
+            Device::CRSF(crsf_device) => Some(crsf_device.receiver()),
+            Device::IBUS(ibus_device) => Some(ibus_device.receiver()),
+            _ => None,
+        }
+    }
 }